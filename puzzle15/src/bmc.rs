@@ -0,0 +1,171 @@
+use crate::circuits::{build_puzzle_15, move_from_code, pos_to_index};
+use crate::goal::Goal;
+use crate::search::trivial_report;
+use crate::{GameState, SolveOutcome, SolveReport, SolveStats};
+use baa::{BitVecOps, Value};
+use patronus::expr::{Context, TypeCheck};
+use patronus::mc::{
+    check_assuming, check_assuming_end, get_smt_value, TransitionSystemEncoding, UnrollSmtEncoding,
+    BITWUZLA_CMD, YICES2_CMD,
+};
+use std::time::Instant;
+
+pub use patronus::mc::SmtSolverCmd;
+
+/// Runs `z3` reading SMT-LIB from stdin.
+pub const Z3_CMD: SmtSolverCmd = SmtSolverCmd {
+    name: "z3",
+    args: &["-in", "-smt2"],
+    supports_uf: true,
+    supports_check_assuming: true,
+};
+
+/// Returns the first of bitwuzla, z3 and yices that is on the `PATH`.
+pub fn find_smt_solver() -> Option<SmtSolverCmd> {
+    let path = std::env::var_os("PATH")?;
+    [BITWUZLA_CMD, Z3_CMD, YICES2_CMD]
+        .into_iter()
+        .find(|solver| std::env::split_paths(&path).any(|dir| dir.join(solver.name).is_file()))
+}
+
+/// Finds the minimal number of moves needed to get from one state to the other by bounded model
+/// checking: the `build_puzzle_15` circuit starting at `from` is unrolled one step at a time and
+/// after every step `solver` is asked for moves that make the tiles equal `to`. The circuit
+/// ignores impossible moves, so the first step count that works is the length of a shortest path.
+/// Gives up with `SolveOutcome::NoPathWithin(max_moves)` after `max_moves` steps. Fails if the
+/// solver cannot be started, stops answering or answers with moves that are impossible or don't
+/// lead to `to`.
+/// No states are expanded one by one, the stats count the solver queries and unrolled steps
+/// instead.
+pub fn find_shortest_path_bmc(
+    from: GameState,
+    to: GameState,
+    solver: SmtSolverCmd,
+    max_moves: u64,
+) -> std::io::Result<SolveReport> {
+    let start = Instant::now();
+    if let Some(report) = trivial_report(&from, &Goal::from(&to), start) {
+        return Ok(report);
+    }
+
+    let mut ctx = Context::default();
+    let (sys, positions, mov) = build_puzzle_15(&mut ctx, Some(&from));
+    let mut smt_ctx = easy_smt::ContextBuilder::new()
+        .solver(solver.name, solver.args)
+        .build()?;
+    smt_ctx.set_logic("QF_BV")?;
+    let mut enc = UnrollSmtEncoding::new(&mut ctx, &sys, false);
+    enc.define_header(&mut smt_ctx)?;
+    enc.init_at(&mut ctx, &mut smt_ctx, 0)?;
+
+    let mut stats = SolveStats::default();
+    for k in 0..=max_moves {
+        let mut reached = vec![];
+        for x in 0..4 {
+            for y in 0..4 {
+                let tile = enc.get_at(&ctx, &mut smt_ctx, positions[pos_to_index(x, y)], k);
+                let expected = smt_ctx.binary(4, to.get(x, y).unwrap_or(0));
+                reached.push(smt_ctx.eq(tile, expected));
+            }
+        }
+        let reached = smt_ctx.and_many(reached);
+        stats.solver_queries += 1;
+        stats.unroll_depth = k;
+        if check_assuming(&mut smt_ctx, reached, &solver)? == easy_smt::Response::Sat {
+            let mut path = Vec::with_capacity(k as usize);
+            for step in 0..k {
+                let code = enc.get_at(&ctx, &mut smt_ctx, mov, step);
+                let m = match get_smt_value(&mut smt_ctx, code, mov.get_type(&ctx))? {
+                    Value::BitVec(code) => code.to_u64().and_then(move_from_code),
+                    Value::Array(_) => None,
+                };
+                path.push(m.ok_or_else(|| invalid_witness("the solver returned an unknown move"))?);
+            }
+            // the circuit ignores impossible moves, a shortest path has none of them
+            let mut replay = from;
+            if replay.perform_moves(&path) != path.len() {
+                return Err(invalid_witness(
+                    "the solver's moves include impossible ones",
+                ));
+            }
+            if replay != to {
+                return Err(invalid_witness("the solver's moves don't lead to the goal"));
+            }
+            return Ok(SolveReport::new(SolveOutcome::Solved(path), stats, start));
+        }
+        check_assuming_end(&mut smt_ctx, &solver)?;
+        if k < max_moves {
+            enc.unroll(&mut ctx, &mut smt_ctx)?;
+        }
+    }
+    let outcome = SolveOutcome::NoPathWithin(max_moves);
+    Ok(SolveReport::new(outcome, stats, start))
+}
+
+fn invalid_witness(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_shortest_path, Move};
+
+    #[test]
+    fn test_without_solver() {
+        // the trivial cases are answered before starting the solver
+        let missing = SmtSolverCmd {
+            name: "there-is-no-such-solver",
+            ..Z3_CMD
+        };
+        let report =
+            find_shortest_path_bmc(GameState::default(), GameState::default(), missing, 10)
+                .unwrap();
+        assert_eq!(report.path(), Some(&[][..]));
+        let mut swapped = GameState::default();
+        swapped.swap(0, 0, 1, 0);
+        let report = find_shortest_path_bmc(GameState::default(), swapped, missing, 10).unwrap();
+        assert_eq!(report.outcome, SolveOutcome::Unsolvable);
+
+        let mut state = GameState::default();
+        state.perform_move(Move::LeftToRight);
+        assert!(find_shortest_path_bmc(GameState::default(), state, missing, 10).is_err());
+    }
+
+    /// Checks the solver against `find_shortest_path`, only where one of the solvers is on the
+    /// `PATH`.
+    #[test]
+    fn test_bmc() {
+        let Some(solver) = find_smt_solver() else {
+            println!("no bitwuzla, z3 or yices on the PATH, skipping");
+            return;
+        };
+        for seed in 0..4 {
+            // start somewhere else than the default state for half of them
+            let from = GameState::random_walk(seed as usize % 2 * 10, seed).0;
+            let mut to = from.clone();
+            to.perform_moves(&GameState::random_walk(4, seed + 10).1);
+            let report = find_shortest_path_bmc(from.clone(), to.clone(), solver, 4).unwrap();
+            let path = report.path().unwrap();
+            let mut state = from.clone();
+            assert_eq!(state.perform_moves(path), path.len());
+            assert_eq!(state, to);
+            let shortest = find_shortest_path(from, to).into_path().unwrap();
+            assert_eq!(path.len(), shortest.len());
+        }
+
+        let (to, _) = GameState::random_walk(12, 0);
+        let shortest = find_shortest_path(GameState::default(), to.clone()).into_path();
+        assert!(shortest.unwrap().len() > 2);
+        let report = find_shortest_path_bmc(GameState::default(), to, solver, 2).unwrap();
+        assert_eq!(report.outcome, SolveOutcome::NoPathWithin(2));
+        assert_eq!(
+            (report.stats.solver_queries, report.stats.unroll_depth),
+            (3, 2)
+        );
+        assert_eq!(
+            (report.stats.nodes_expanded, report.stats.peak_frontier),
+            (0, 0)
+        );
+    }
+}
@@ -1,10 +1,17 @@
-use crate::{GameState, Move};
+//! The puzzle as a circuit: a `TransitionSystem` with one state per position and the move as
+//! its input, together with the helpers to move between it and `GameState` and a harness that
+//! checks it against the software model.
+
+use crate::scramble::Rng;
+use crate::{Board, GameState, Move};
 use baa::*;
 use patronus::expr::*;
-use patronus::sim::interpreter::*;
+use patronus::sim::interpreter::{Interpreter, Simulator};
 use patronus::system::*;
 
 /// Generates a counter from 0 to (2**width) - 1.
+// the counters are only exercised by the tests
+#[allow(dead_code)]
 fn build_counter_0(ctx: &mut Context, width: WidthInt) -> TransitionSystem {
     // define a symbol to hold the count value
     let count = ctx.bv_symbol("count", width);
@@ -41,6 +48,8 @@ fn build_counter_0(ctx: &mut Context, width: WidthInt) -> TransitionSystem {
 ///                or multiplexers in hardware: https://en.wikipedia.org/wiki/Multiplexer
 ///          Hint: to create a constant other than 0 or 1, use:
 ///                `ctx.bv_lit(&BitVecValue::from_u64(value, width))`
+// the counters are only exercised by the tests
+#[allow(dead_code)]
 fn build_counter_1(ctx: &mut Context, width: WidthInt, max_value: u64) -> TransitionSystem {
     // define a symbol to hold the count value
     let count = ctx.bv_symbol("count", width);
@@ -48,7 +57,6 @@ fn build_counter_1(ctx: &mut Context, width: WidthInt, max_value: u64) -> Transi
     // define how the count gets updated:
     // count' := count + 1
     // `ctx.build` is used here, because we are building a nested expression
-    let count_init = ctx.zero(width);
     let count_max: ExprRef = ctx.bv_lit(&BitVecValue::from_u64(max_value, width));
 
     //Played around to see how different comtext methods work, this count_next also works it is
@@ -88,6 +96,8 @@ fn build_counter_1(ctx: &mut Context, width: WidthInt, max_value: u64) -> Transi
 }
 
 /// Task #2: now the counter gets an `en` input which controls whether the counter increments or not
+// the counters are only exercised by the tests
+#[allow(dead_code)]
 fn build_counter_2(ctx: &mut Context, width: WidthInt, max_value: u64) -> TransitionSystem {
     // define a symbol to hold the count value
     let count = ctx.bv_symbol("count", width);
@@ -131,92 +141,136 @@ fn build_counter_2(ctx: &mut Context, width: WidthInt, max_value: u64) -> Transi
     sys
 }
 
-const MOVES: [Move; 4] = [
-    Move::LeftToRight,
-    Move::RightToLeft,
-    Move::TopToBottom,
-    Move::BottomToTop,
-];
-
 /// used to index into `positions` array
-fn pos_to_index(x: u8, y: u8) -> usize {
-    y as usize * 4 + x as usize
+pub fn pos_to_index(x: u8, y: u8) -> usize {
+    board_pos_to_index::<4>(x, y)
+}
+
+/// Index of the position at column `x` and row `y` in the `positions` of `build_puzzle` for a
+/// board `W` tiles wide, row by row like the cells of `Board`.
+pub fn board_pos_to_index<const W: usize>(x: u8, y: u8) -> usize {
+    y as usize * W + x as usize
+}
+
+/// Number of bits the positions of a `W`x`H` circuit need to hold every tile and 0 for the
+/// blank, ⌈log2(W·H)⌉.
+pub fn tile_width<const W: usize, const H: usize>() -> WidthInt {
+    ((W * H).max(2) - 1).ilog2() + 1
+}
+
+/// Returns the literal tile value of every position of `state`, in the order of the `positions`
+/// of `build_puzzle_15`, to be used as their init expressions. The blank is 0.
+pub fn game_state_to_init(ctx: &mut Context, state: &GameState) -> Vec<ExprRef> {
+    board_to_init(ctx, state)
+}
+
+/// Same as `game_state_to_init` for the circuits of `build_puzzle`.
+pub fn board_to_init<const W: usize, const H: usize>(
+    ctx: &mut Context,
+    state: &Board<W, H>,
+) -> Vec<ExprRef> {
+    let mut init = vec![];
+    for y in 0..H as u8 {
+        for x in 0..W as u8 {
+            let tile = state.get(x, y).unwrap_or(0) as u64;
+            init.push(ctx.bv_lit(&BitVecValue::from_u64(tile, tile_width::<W, H>())));
+        }
+    }
+    init
 }
 
 /// Task #3: implement a circuit that executes the puzzle15 game
-fn build_puzzle_15(ctx: &mut Context) -> (TransitionSystem, Vec<ExprRef>, ExprRef) {
-    let mut sys = TransitionSystem::new("puzzle15".to_string());
+/// Returns the system, the `positions` and the `move` input. The positions start out as `init`,
+/// or without an init expression if it is None. Such positions can hold any value when model
+/// checking, while the interpreter starts them at 0.
+pub fn build_puzzle_15(
+    ctx: &mut Context,
+    init: Option<&GameState>,
+) -> (TransitionSystem, Vec<ExprRef>, ExprRef) {
+    build_puzzle(ctx, init)
+}
 
-    // we encode the move as an input with 0..3 corresponding to the `[MOVES]`
+/// Same as `build_puzzle_15` for a board `W` tiles wide and `H` tiles high. The positions are
+/// `tile_width` bits wide.
+pub fn build_puzzle<const W: usize, const H: usize>(
+    ctx: &mut Context,
+    init: Option<&Board<W, H>>,
+) -> (TransitionSystem, Vec<ExprRef>, ExprRef) {
+    let name = match (W, H) {
+        (4, 4) => "puzzle15".to_string(),
+        _ => format!("puzzle{W}x{H}"),
+    };
+    let mut sys = TransitionSystem::new(name);
+    let width = tile_width::<W, H>();
+    let index = board_pos_to_index::<W>;
+    let (w, h) = (W as u8, H as u8);
+
+    // we encode the move as an input with 0..3 corresponding to `Move::ALL`
     let mov = ctx.bv_symbol("move", 2);
-    sys.add_input(&ctx, mov);
+    sys.add_input(ctx, mov);
     let move_left_to_right = ctx.build(|c| c.bv_equal(mov, c.bit_vec_val(0, 2)));
     let move_right_to_left = ctx.build(|c| c.bv_equal(mov, c.bit_vec_val(1, 2)));
     let move_top_to_bottom = ctx.build(|c| c.bv_equal(mov, c.bit_vec_val(2, 2)));
     let move_bottom_to_top = ctx.build(|c| c.bv_equal(mov, c.bit_vec_val(3, 2)));
 
-    // we create one state for every position, we represent the kind of tile with numbers from 0 to 15
+    // we create one state for every position, we represent the kind of tile with numbers from 0
+    // to W * H - 1
     let mut positions = vec![];
-    let mut positions_init = vec![];
     let mut is_empty = vec![];
-    let init_state = GameState::default();
-    for y in 0..4 {
-        for x in 0..4 {
-            let symbol = ctx.bv_symbol(&format!("pos_{x}_{y}"), 4);
+    for y in 0..h {
+        for x in 0..w {
+            let symbol = ctx.bv_symbol(&format!("pos_{x}_{y}"), width);
             positions.push(symbol);
-            let init_value = BitVecValue::from_u64(init_state.get(x, y).unwrap_or(0) as u64, 4);
-            positions_init.push(ctx.bv_lit(&init_value));
             // condition to see if the tile is empty
-            is_empty.push(ctx.build(|c| c.bv_equal(symbol, c.zero(4))));
+            is_empty.push(ctx.build(|c| c.bv_equal(symbol, c.zero(width))));
         }
     }
 
     // define the next state function for every position
     let mut positions_next = vec![];
-    for y in 0..4 {
-        for x in 0..4 {
-            let position = positions[pos_to_index(x, y)];
+    for y in 0..h {
+        for x in 0..w {
+            let position = positions[index(x, y)];
 
-            // TODO: current we just assign the old tile value, how do we correctly compute the next tile value?
             let mut cond: Vec<(ExprRef, ExprRef)> = vec![];
             if x > 0 {
                 cond.push((
-                    ctx.and(move_left_to_right, is_empty[pos_to_index(x, y)]),
-                    positions[pos_to_index(x - 1, y)],
+                    ctx.and(move_left_to_right, is_empty[index(x, y)]),
+                    positions[index(x - 1, y)],
                 ));
                 cond.push((
-                    ctx.and(move_right_to_left, is_empty[pos_to_index(x - 1, y)]),
-                    ctx.zero(4),
+                    ctx.and(move_right_to_left, is_empty[index(x - 1, y)]),
+                    ctx.zero(width),
                 ));
             }
-            if x < 3 {
+            if x + 1 < w {
                 cond.push((
-                    ctx.and(move_right_to_left, is_empty[pos_to_index(x, y)]),
-                    positions[pos_to_index(x + 1, y)],
+                    ctx.and(move_right_to_left, is_empty[index(x, y)]),
+                    positions[index(x + 1, y)],
                 ));
                 cond.push((
-                    ctx.and(move_left_to_right, is_empty[pos_to_index(x + 1, y)]),
-                    ctx.zero(4),
+                    ctx.and(move_left_to_right, is_empty[index(x + 1, y)]),
+                    ctx.zero(width),
                 ));
             }
             if y > 0 {
                 cond.push((
-                    ctx.and(move_top_to_bottom, is_empty[pos_to_index(x, y)]),
-                    positions[pos_to_index(x, y - 1)],
+                    ctx.and(move_top_to_bottom, is_empty[index(x, y)]),
+                    positions[index(x, y - 1)],
                 ));
                 cond.push((
-                    ctx.and(move_bottom_to_top, is_empty[pos_to_index(x, y - 1)]),
-                    ctx.zero(4),
+                    ctx.and(move_bottom_to_top, is_empty[index(x, y - 1)]),
+                    ctx.zero(width),
                 ));
             }
-            if y < 3 {
+            if y + 1 < h {
                 cond.push((
-                    ctx.and(move_bottom_to_top, is_empty[pos_to_index(x, y)]),
-                    positions[pos_to_index(x, y + 1)],
+                    ctx.and(move_bottom_to_top, is_empty[index(x, y)]),
+                    positions[index(x, y + 1)],
                 ));
                 cond.push((
-                    ctx.and(move_top_to_bottom, is_empty[pos_to_index(x, y + 1)]),
-                    ctx.zero(4),
+                    ctx.and(move_top_to_bottom, is_empty[index(x, y + 1)]),
+                    ctx.zero(width),
                 ));
             }
             let position_next = ctx.build(|c| {
@@ -232,16 +286,20 @@ fn build_puzzle_15(ctx: &mut Context) -> (TransitionSystem, Vec<ExprRef>, ExprRe
     }
 
     // create states
+    let positions_init = match init {
+        Some(state) => board_to_init(ctx, state).into_iter().map(Some).collect(),
+        None => vec![None; positions.len()],
+    };
     for (pos, (next, init)) in positions
         .iter()
-        .zip(positions_next.into_iter().zip(positions_init.into_iter()))
+        .zip(positions_next.into_iter().zip(positions_init))
     {
         sys.add_state(
-            &ctx,
+            ctx,
             State {
                 symbol: *pos,
                 next: Some(next),
-                init: Some(init),
+                init,
             },
         );
     }
@@ -249,11 +307,176 @@ fn build_puzzle_15(ctx: &mut Context) -> (TransitionSystem, Vec<ExprRef>, ExprRe
     (sys, positions, mov)
 }
 
+/// The value of the `move` input for `m`, its index in `Move::ALL`.
+pub fn move_to_code(m: Move) -> u64 {
+    match m {
+        Move::LeftToRight => 0,
+        Move::RightToLeft => 1,
+        Move::TopToBottom => 2,
+        Move::BottomToTop => 3,
+    }
+}
+
+/// The move for a value of the `move` input, None if it is out of range.
+pub fn move_from_code(code: u64) -> Option<Move> {
+    Move::ALL.get(usize::try_from(code).ok()?).copied()
+}
+
+/// `m` as a 2-bit value, ready for `Simulator::set` on the `move` input.
+pub fn encode_move(m: Move) -> BitVecValue {
+    BitVecValue::from_u64(move_to_code(m), 2)
+}
+
+impl<const W: usize, const H: usize> Board<W, H> {
+    /// Creates a board from a circuit's simulation state.
+    /// Panics if the simulator does not know the `positions`.
+    pub fn from_circuit(positions: &[ExprRef], simulator: &impl Simulator) -> Self {
+        let mut state = Self::default();
+        for x in 0..W as u8 {
+            for y in 0..H as u8 {
+                let value = simulator
+                    .get(positions[board_pos_to_index::<W>(x, y)])
+                    .unwrap()
+                    .to_u64()
+                    .unwrap();
+                state.set(x, y, if value == 0 { None } else { Some(value as u8) });
+            }
+        }
+        state
+    }
+
+    /// Overwrites the `positions` of a running simulation with this state, the next step starts
+    /// from here.
+    pub fn load_into_circuit(&self, positions: &[ExprRef], simulator: &mut impl Simulator) {
+        for y in 0..H as u8 {
+            for x in 0..W as u8 {
+                let tile = self.get(x, y).unwrap_or(0) as u64;
+                let value = BitVecValue::from_u64(tile, tile_width::<W, H>());
+                simulator.set(positions[board_pos_to_index::<W>(x, y)], &value);
+            }
+        }
+    }
+}
+
+/// The first step where the circuit and the software model disagree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence<const W: usize = 4, const H: usize = 4> {
+    pub start: Board<W, H>,
+    /// the moves up to and including the one that made them disagree, empty if they already
+    /// disagreed about the start
+    pub moves: Vec<Move>,
+    pub model: Board<W, H>,
+    pub circuit: Board<W, H>,
+}
+
+impl<const W: usize, const H: usize> std::fmt::Display for Divergence<W, H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.moves.split_last() {
+            Some((m, before)) => writeln!(
+                f,
+                "step {}: {m} after '{}' from",
+                self.moves.len(),
+                Move::format_sequence(before)
+            )?,
+            None => writeln!(f, "step 0: loading")?,
+        }
+        write!(f, "{}", self.start)?;
+        write!(f, "gives in the circuit\n{}", self.circuit)?;
+        write!(f, "but in the model\n{}", self.model)
+    }
+}
+
+impl<const W: usize, const H: usize> std::error::Error for Divergence<W, H> {}
+
+/// Counters collected while co-simulating.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CosimStats {
+    pub runs: usize,
+    pub steps: usize,
+    /// moves that were not possible and had to leave both boards as they were
+    pub blocked: usize,
+}
+
+/// Drives the circuit, given by its system, `positions` and `move` input like `build_puzzle`
+/// returns them, and a software `Board` in lock-step: loads `start` into both, then makes every
+/// move on both and compares them after each one. The move is applied to the circuit even if it
+/// is not possible, the circuit has to ignore it like the model does.
+pub fn cosimulate<const W: usize, const H: usize>(
+    ctx: &Context,
+    sys: &TransitionSystem,
+    (positions, mov): (&[ExprRef], ExprRef),
+    start: &Board<W, H>,
+    moves: impl IntoIterator<Item = Move>,
+) -> Result<CosimStats, Divergence<W, H>> {
+    let mut simulator = Interpreter::new(ctx, sys);
+    simulator.init();
+    let mut stats = CosimStats::default();
+    run_in_lock_step((positions, mov), &mut simulator, start, moves, &mut stats)?;
+    Ok(stats)
+}
+
+/// Co-simulates the circuit of `build_puzzle` against the software model for `runs` random
+/// solvable starts with `len` random moves each, about a quarter of which are not possible on
+/// average. The same seed always gives the same runs.
+pub fn cosimulate_random<const W: usize, const H: usize>(
+    seed: u64,
+    runs: usize,
+    len: usize,
+) -> Result<CosimStats, Divergence<W, H>> {
+    let mut ctx = Context::default();
+    let (sys, positions, mov) = build_puzzle::<W, H>(&mut ctx, None);
+    let mut simulator = Interpreter::new(&ctx, &sys);
+    simulator.init();
+    let mut rng = Rng(seed);
+    let mut stats = CosimStats::default();
+    for _ in 0..runs {
+        let start = Board::random_solvable(rng.next());
+        let moves: Vec<Move> = (0..len).map(|_| Move::ALL[rng.below(4)]).collect();
+        run_in_lock_step((&positions, mov), &mut simulator, &start, moves, &mut stats)?;
+    }
+    Ok(stats)
+}
+
+fn run_in_lock_step<const W: usize, const H: usize>(
+    (positions, mov): (&[ExprRef], ExprRef),
+    simulator: &mut Interpreter,
+    start: &Board<W, H>,
+    moves: impl IntoIterator<Item = Move>,
+    stats: &mut CosimStats,
+) -> Result<(), Divergence<W, H>> {
+    stats.runs += 1;
+    start.load_into_circuit(positions, simulator);
+    let mut model = start.clone();
+    let mut made = vec![];
+    let compare = |made: &[Move], model: &Board<W, H>, simulator: &Interpreter| {
+        let circuit = Board::from_circuit(positions, simulator);
+        if circuit == *model {
+            return Ok(());
+        }
+        Err(Divergence {
+            start: start.clone(),
+            moves: made.to_vec(),
+            model: model.clone(),
+            circuit,
+        })
+    };
+    compare(&made, &model, simulator)?;
+    for m in moves {
+        if !model.perform_move(m) {
+            stats.blocked += 1;
+        }
+        simulator.set(mov, &encode_move(m));
+        simulator.step();
+        stats.steps += 1;
+        made.push(m);
+        compare(&made, &model, simulator)?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
 
-    use patronus::sim;
-
     use super::*;
 
     #[test]
@@ -352,48 +575,19 @@ state count : bv<2>
         }
     }
 
-    fn move_to_code(m: Move) -> u64 {
-        match m {
-            Move::LeftToRight => 0,
-            Move::RightToLeft => 1,
-            Move::TopToBottom => 2,
-            Move::BottomToTop => 3,
-        }
-    }
-    impl GameState {
-        /// Creates a GameState from a circuit's simulation state.
-        pub fn from_circuit(ctx: &Context, positions: &[ExprRef], simulator: &Interpreter) -> Self {
-            let mut board = [[None; 4]; 4];
-            for x in 0..4 {
-                // Iterate columns first to match circuit's column-major
-                for y in 0..4 {
-                    // Assign to row-major GameState format
-                    let pos_index = y * 4 + x; // Use column-major indexing
-                    let value = simulator
-                        .get(positions[pos_index])
-                        .unwrap()
-                        .to_u64()
-                        .unwrap();
-                    board[x][y] = if value == 0 { None } else { Some(value as u8) };
-                }
-            }
-            GameState { board }
-        }
-    }
-
     #[test]
     fn test_puzzle15() {
         let mut ctx = Context::default();
-        let (sys, positions, mov) = build_puzzle_15(&mut ctx);
+        let (sys, positions, mov) = build_puzzle_15(&mut ctx, Some(&GameState::default()));
         // we print out the puzzle to help you debug
         println!("{}", sys.serialize_to_str(&ctx));
         let mut simulator = Interpreter::new(&ctx, &sys);
         simulator.init(); //loads values initially assigned
 
-        let circuit_game_state = GameState::from_circuit(&ctx, &positions, &simulator);
+        let circuit_game_state = GameState::from_circuit(&positions, &simulator);
         let default_game_state = GameState::default();
-        print!("{}\n", circuit_game_state);
-        print!("{}\n", default_game_state);
+        println!("{}", circuit_game_state);
+        println!("{}", default_game_state);
         assert_eq!(circuit_game_state, default_game_state);
 
         let move_seq = [Move::TopToBottom, Move::LeftToRight, Move::LeftToRight];
@@ -403,18 +597,140 @@ state count : bv<2>
             simulator.set(mov, &symbolic_move);
             simulator.step()
         }
-        let expected_state = GameState {
-            board: [
-                [Some(1), Some(5), Some(9), Some(13)],
-                [Some(2), Some(6), None, Some(14)],
-                [Some(3), Some(7), Some(10), Some(15)],
-                [Some(4), Some(8), Some(11), Some(12)],
-            ],
-        };
-
-        let circuit_game_state = GameState::from_circuit(&ctx, &positions, &simulator);
+        let expected_state = GameState::from_board([
+            [Some(1), Some(5), Some(9), Some(13)],
+            [Some(2), Some(6), None, Some(14)],
+            [Some(3), Some(7), Some(10), Some(15)],
+            [Some(4), Some(8), Some(11), Some(12)],
+        ]);
+
+        let circuit_game_state = GameState::from_circuit(&positions, &simulator);
         println!("Final Circuit State:\n{}", circuit_game_state);
         println!("Expected State:\n{}", expected_state);
         assert_eq!(circuit_game_state, expected_state);
     }
+
+    #[test]
+    fn test_puzzle15_init() {
+        let mut ctx = Context::default();
+        let (start, _) = GameState::random_walk(30, 5);
+        let (sys, positions, mov) = build_puzzle_15(&mut ctx, Some(&start));
+        let init = game_state_to_init(&mut ctx, &start);
+        let init_of = |pos| sys.states().find(|(_, s)| s.symbol == pos).unwrap().1.init;
+        assert!(positions
+            .iter()
+            .zip(&init)
+            .all(|(&pos, &i)| init_of(pos) == Some(i)));
+
+        let mut simulator = Interpreter::new(&ctx, &sys);
+        simulator.init();
+        assert_eq!(GameState::from_circuit(&positions, &simulator), start);
+
+        let mut expected = start.clone();
+        for m in [Move::TopToBottom, Move::RightToLeft, Move::RightToLeft] {
+            expected.perform_move(m);
+            simulator.set(mov, &encode_move(m));
+            simulator.step();
+        }
+        assert_eq!(GameState::from_circuit(&positions, &simulator), expected);
+
+        // without an init the interpreter starts with every position empty
+        let mut ctx = Context::default();
+        let (sys, positions, _) = build_puzzle_15(&mut ctx, None);
+        assert!(sys.states().all(|(_, s)| s.init.is_none()));
+        let mut simulator = Interpreter::new(&ctx, &sys);
+        simulator.init();
+        assert!(positions
+            .iter()
+            .all(|&pos| simulator.get(pos).unwrap().is_zero()));
+    }
+
+    #[test]
+    fn test_move_codes() {
+        for (i, m) in Move::ALL.into_iter().enumerate() {
+            assert_eq!(move_to_code(m), i as u64);
+            assert_eq!(move_from_code(i as u64), Some(m));
+            assert_eq!(encode_move(m).to_u64(), Some(i as u64));
+            assert_eq!(encode_move(m).width(), 2);
+        }
+        assert_eq!(move_from_code(4), None);
+    }
+
+    #[test]
+    fn test_load_into_circuit() {
+        let mut ctx = Context::default();
+        let (sys, positions, mov) = build_puzzle_15(&mut ctx, Some(&GameState::default()));
+        let mut simulator = Interpreter::new(&ctx, &sys);
+        simulator.init();
+        let (state, _) = GameState::random_walk(25, 3);
+        state.load_into_circuit(&positions, &mut simulator);
+        assert_eq!(GameState::from_circuit(&positions, &simulator), state);
+
+        let mut expected = state.clone();
+        let m = expected.legal_moves().next().unwrap();
+        expected.perform_move(m);
+        simulator.set(mov, &encode_move(m));
+        simulator.step();
+        assert_eq!(GameState::from_circuit(&positions, &simulator), expected);
+    }
+
+    #[test]
+    fn test_board_sizes() {
+        assert_eq!(tile_width::<2, 2>(), 2);
+        assert_eq!(tile_width::<3, 3>(), 4);
+        assert_eq!(tile_width::<4, 4>(), 4);
+        assert_eq!(tile_width::<5, 5>(), 5);
+        assert_eq!(tile_width::<2, 4>(), 3);
+        assert_eq!(board_pos_to_index::<5>(2, 3), 17);
+
+        let mut ctx = Context::default();
+        let (sys, positions, _) = build_puzzle::<5, 5>(&mut ctx, None);
+        assert_eq!(positions.len(), 25);
+        assert!(positions
+            .iter()
+            .all(|&pos| pos.get_bv_type(&ctx) == Some(5)));
+        assert_eq!(sys.name, "puzzle5x5");
+
+        cosimulate_random::<2, 2>(0, 20, 50).unwrap();
+        cosimulate_random::<3, 3>(1, 20, 50).unwrap();
+        cosimulate_random::<5, 5>(2, 20, 50).unwrap();
+        cosimulate_random::<3, 2>(3, 20, 50).unwrap();
+    }
+
+    #[test]
+    fn test_cosimulate_random() {
+        let stats = cosimulate_random::<4, 4>(7, 2000, 5).unwrap();
+        assert_eq!((stats.runs, stats.steps), (2000, 10000));
+        assert!((1500..3500).contains(&stats.blocked));
+    }
+
+    #[test]
+    fn test_cosimulate() {
+        let mut ctx = Context::default();
+        let (sys, mut positions, mov) = build_puzzle_15(&mut ctx, None);
+        let (start, moves) = (GameState::default(), GameState::random_walk(40, 9).1);
+        let stats = cosimulate(&ctx, &sys, (&positions, mov), &start, moves).unwrap();
+        assert_eq!((stats.runs, stats.steps, stats.blocked), (1, 40, 0));
+
+        // a circuit with two positions mixed up reads and writes consistently, but moves the
+        // wrong tiles
+        positions.swap(14, 15);
+        let moves = Move::parse_sequence("UR").unwrap();
+        let divergence =
+            cosimulate(&ctx, &sys, (&positions, mov), &GameState::default(), moves).unwrap_err();
+        assert_eq!(divergence.moves, vec![Move::BottomToTop, Move::LeftToRight]);
+        let mut model = GameState::default();
+        model.perform_move(Move::LeftToRight);
+        assert_eq!(divergence.model, model);
+        let mut circuit = GameState::default();
+        circuit.swap(1, 3, 3, 3);
+        assert_eq!(divergence.circuit, circuit);
+        assert_eq!(
+            divergence.to_string(),
+            format!(
+                "step 2: R after 'U' from\n{}gives in the circuit\n{circuit}but in the model\n{model}",
+                GameState::default()
+            )
+        );
+    }
 }
@@ -0,0 +1,267 @@
+use crate::search::SearchState;
+use crate::Board;
+
+/// What a goal asks for in one cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GoalCell {
+    /// The cell has to hold this tile.
+    Tile(u8),
+    /// The cell has to be the blank.
+    Blank,
+    /// Any tile or the blank will do.
+    Any,
+}
+
+/// Describes the states a search should end in: every cell either needs a certain tile, needs
+/// to be the blank, or doesn't matter. Tiles that no cell asks for can end up anywhere.
+/// Indexed by column, then row, like `Board`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Goal<const W: usize = 4, const H: usize = 4> {
+    cells: [[GoalCell; H]; W],
+}
+
+/// The default state, with every cell required.
+impl<const W: usize, const H: usize> Default for Goal<W, H> {
+    fn default() -> Self {
+        Goal::from(&Board::default())
+    }
+}
+
+/// Requires exactly the given state.
+impl<const W: usize, const H: usize> From<&Board<W, H>> for Goal<W, H> {
+    fn from(state: &Board<W, H>) -> Self {
+        let cells = std::array::from_fn(|x| {
+            std::array::from_fn(|y| match state.get(x as u8, y as u8) {
+                Some(tile) => GoalCell::Tile(tile),
+                None => GoalCell::Blank,
+            })
+        });
+        Goal { cells }
+    }
+}
+
+impl<const W: usize, const H: usize> Goal<W, H> {
+    /// A goal that every state reaches, to be narrowed down with `set`.
+    pub fn any() -> Self {
+        Goal {
+            cells: [[GoalCell::Any; H]; W],
+        }
+    }
+
+    /// Requires the `tiles` to be where they are in `state` and ignores all other cells. For
+    /// example `Goal::partial(&GameState::default(), &[1, 2, 3, 4])` asks for the top row.
+    pub fn partial(state: &Board<W, H>, tiles: &[u8]) -> Self {
+        let mut goal = Goal::any();
+        for x in 0..W as u8 {
+            for y in 0..H as u8 {
+                if let Some(tile) = state.get(x, y).filter(|tile| tiles.contains(tile)) {
+                    goal.set(x, y, GoalCell::Tile(tile));
+                }
+            }
+        }
+        goal
+    }
+
+    pub fn set(&mut self, x: u8, y: u8, cell: GoalCell) {
+        self.cells[x as usize][y as usize] = cell;
+    }
+
+    pub fn get(&self, x: u8, y: u8) -> GoalCell {
+        self.cells[x as usize][y as usize]
+    }
+
+    /// The only state that reaches the goal if it requires every cell.
+    pub fn state(&self) -> Option<Board<W, H>> {
+        let mut board = [[None; H]; W];
+        let mut count = 0;
+        for (index, tile) in self.required() {
+            board[index % W][index / W] = (tile != 0).then_some(tile);
+            count += 1;
+        }
+        (count == W * H).then(|| Board::from_board(board))
+    }
+
+    /// Returns the row-major index of every required cell together with its tile, 0 for the
+    /// blank.
+    pub(crate) fn required(&self) -> impl Iterator<Item = (usize, u8)> + '_ {
+        (0..W * H).filter_map(|index| match self.cells[index % W][index / W] {
+            GoalCell::Tile(tile) => Some((index, tile)),
+            GoalCell::Blank => Some((index, 0)),
+            GoalCell::Any => None,
+        })
+    }
+
+    /// Returns true if `state` has every required tile and the blank where the goal wants them.
+    pub fn is_reached(&self, state: &Board<W, H>) -> bool {
+        self.is_reached_by(state)
+    }
+
+    pub(crate) fn is_reached_by(&self, state: &impl SearchState<W, H>) -> bool {
+        self.required()
+            .all(|(index, tile)| state.tile(index) == tile)
+    }
+
+    /// Returns true if every required tile is between 1 and `MAX_TILE` and no tile is required
+    /// twice, and at most one cell has to be the blank.
+    pub fn is_valid(&self) -> bool {
+        if self
+            .cells
+            .iter()
+            .flatten()
+            .any(|&cell| cell == GoalCell::Tile(0))
+        {
+            return false;
+        }
+        let mut seen = vec![false; Board::<W, H>::MAX_TILE as usize + 1];
+        for (_, tile) in self.required() {
+            if tile as usize >= seen.len() || std::mem::replace(&mut seen[tile as usize], true) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Returns true if some state that reaches the goal can be reached from `state`.
+    /// Only parity separates the states with the same tiles, so unless the board is a single
+    /// line, two tiles that can go anywhere can always be swapped to fix it. Otherwise there are
+    /// just a few ways to fill the open cells, which are tried one by one.
+    /// Returns false if the goal or the state are not valid.
+    pub fn is_reachable_from(&self, state: &Board<W, H>) -> bool {
+        if !self.is_valid() || !state.all_tiles_unique() {
+            return false;
+        }
+        let mut required = vec![false; W * H];
+        let mut open_cells = vec![];
+        let mut blank_required = false;
+        let mut cells = [[None; H]; W];
+        for index in 0..W * H {
+            let (x, y) = (index % W, index / W);
+            match self.cells[x][y] {
+                GoalCell::Tile(tile) => {
+                    required[tile as usize] = true;
+                    cells[x][y] = Some(tile);
+                }
+                GoalCell::Blank => blank_required = true,
+                GoalCell::Any => open_cells.push((x, y)),
+            }
+        }
+        // the tiles that are left for the open cells, in the order they have in `state`
+        let free: Vec<u8> = (0..H)
+            .flat_map(|y| (0..W).map(move |x| (x, y)))
+            .filter_map(|(x, y)| state.get(x as u8, y as u8))
+            .filter(|&tile| !required[tile as usize])
+            .collect();
+
+        // try every open cell for the blank, unless it already has a place
+        let blank_cells: Vec<Option<usize>> = if blank_required {
+            vec![None]
+        } else {
+            (0..open_cells.len()).map(Some).collect()
+        };
+        for blank in blank_cells {
+            let mut free = free.clone();
+            for swap in [false, true] {
+                if swap {
+                    if free.len() < 2 {
+                        break;
+                    }
+                    free.swap(0, 1);
+                }
+                let mut completion = cells;
+                let mut tiles = free.iter();
+                for (i, &(x, y)) in open_cells.iter().enumerate() {
+                    if blank != Some(i) {
+                        completion[x][y] = tiles.next().copied();
+                    }
+                }
+                if state.is_solvable_from(&Board::from_board(completion)) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GameState, Move};
+
+    #[test]
+    fn test_reached() {
+        let goal = Goal::default();
+        assert!(goal.is_reached(&GameState::default()));
+        let mut state = GameState::default();
+        state.perform_move(Move::LeftToRight);
+        assert!(!goal.is_reached(&state));
+
+        let top_row = Goal::partial(&GameState::default(), &[1, 2, 3, 4]);
+        assert_eq!(top_row.get(2, 0), GoalCell::Tile(3));
+        assert_eq!(top_row.get(2, 1), GoalCell::Any);
+        assert!(top_row.is_reached(&state));
+        state.perform_moves(&[Move::TopToBottom, Move::TopToBottom, Move::TopToBottom]);
+        assert!(!top_row.is_reached(&state));
+
+        let mut corner = Goal::<3, 3>::any();
+        assert!(corner.is_reached(&Board::default()));
+        corner.set(0, 0, GoalCell::Blank);
+        assert!(!corner.is_reached(&Board::default()));
+    }
+
+    #[test]
+    fn test_valid() {
+        let mut goal = Goal::<2, 2>::any();
+        assert!(goal.is_valid());
+        goal.set(0, 0, GoalCell::Tile(3));
+        goal.set(1, 0, GoalCell::Blank);
+        assert!(goal.is_valid());
+        goal.set(0, 1, GoalCell::Tile(3));
+        assert!(!goal.is_valid());
+        goal.set(0, 1, GoalCell::Blank);
+        assert!(!goal.is_valid());
+        goal.set(0, 1, GoalCell::Tile(4));
+        assert!(!goal.is_valid());
+        goal.set(0, 1, GoalCell::Tile(0));
+        assert!(!goal.is_valid());
+    }
+
+    #[test]
+    fn test_reachable() {
+        let start = GameState::default();
+        assert!(Goal::default().is_reachable_from(&start));
+        assert!(Goal::any().is_reachable_from(&start));
+
+        // swapping two tiles is only impossible as long as nothing else can make up for it
+        let mut swapped = GameState::default();
+        swapped.swap(0, 0, 1, 0);
+        assert!(!Goal::from(&swapped).is_reachable_from(&start));
+        assert!(Goal::partial(&swapped, &[1, 2]).is_reachable_from(&start));
+        let all_but_one: Vec<u8> = (1..15).collect();
+        assert!(!Goal::partial(&swapped, &all_but_one).is_reachable_from(&start));
+        let mut blank_anywhere = Goal::from(&swapped);
+        blank_anywhere.set(3, 3, GoalCell::Any);
+        assert!(!blank_anywhere.is_reachable_from(&start));
+        // moving the blank within the last row and swapping back the tiles there doesn't help
+        let mut last_row_open = Goal::from(&swapped);
+        for x in 2..4 {
+            last_row_open.set(x, 3, GoalCell::Any);
+        }
+        assert!(!last_row_open.is_reachable_from(&start));
+        last_row_open.set(1, 3, GoalCell::Any);
+        assert!(last_row_open.is_reachable_from(&start));
+
+        // a single row keeps the order of the tiles
+        let row = Board::<4, 1>::default();
+        let mut goal = Goal::<4, 1>::any();
+        goal.set(0, 0, GoalCell::Tile(2));
+        assert!(!goal.is_reachable_from(&row));
+        goal.set(0, 0, GoalCell::Blank);
+        goal.set(2, 0, GoalCell::Tile(2));
+        assert!(goal.is_reachable_from(&row));
+
+        let mut invalid = Goal::any();
+        invalid.set(0, 0, GoalCell::Tile(16));
+        assert!(!invalid.is_reachable_from(&start));
+    }
+}
@@ -1,43 +1,93 @@
-mod circuits;
+mod bmc;
+pub mod circuits;
+mod goal;
+mod notation;
+mod packed;
+mod pdb;
+mod play;
+mod rank;
+mod scramble;
+mod search;
+#[cfg(feature = "serde")]
+mod serialize;
+mod session;
 
+pub use bmc::{find_shortest_path_bmc, find_smt_solver, SmtSolverCmd, Z3_CMD};
+pub use goal::{Goal, GoalCell};
+pub use notation::{Notation, ParseMoveError};
+pub use packed::{InvalidTile, PackedBoard, PackedState};
+pub use pdb::{AdditivePdb, PdbError, MAX_PATTERN_SIZE, PARTITION_5_5_5, PARTITION_6_6_3};
+pub use play::{play, Ending, Event, Game, Hint, Key, Keys, HINT_MAX_NODES};
+pub use search::{
+    find_shortest_path_astar, find_shortest_path_astar_pdb, find_shortest_path_astar_to_goal,
+    find_shortest_path_bidirectional, find_shortest_path_idastar,
+    find_shortest_path_idastar_budget, find_shortest_path_idastar_pdb,
+    find_shortest_path_idastar_to_goal, SolveOutcome, SolveReport, SolveStats,
+};
+pub use session::{ParseRecordError, Record, ReplayError, Session};
+
+use notation::{Cell, Placer};
+use search::{trivial_report, Manhattan, SearchState};
+use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::default;
-use std::hash::{Hash, Hasher};
-use std::ptr::eq;
-use std::thread::current;
-use std::{fmt::format, usize};
+use std::str::FromStr;
+use std::time::Instant;
 
-/// Holds information about which tile is in which position.
+/// Holds information about which tile is in which position on a board with `W` columns and `H`
+/// rows. The tiles are numbered from 1 to `W * H - 1`, the remaining cell is the blank.
 /// Should be fairly compact and easy to copy.
-#[derive(Debug, Clone, Hash)] // Add Hash here
-pub struct GameState {
-    board: [[Option<u8>; 4]; 4],
+#[derive(Debug, Clone)]
+pub struct Board<const W: usize, const H: usize> {
+    board: [[Option<u8>; H]; W],
+    /// position of an empty cell, kept up to date by every method that changes the board so
+    /// moves do not have to look for it
+    blank: Option<(u8, u8)>,
 }
 
+/// The classic 15-puzzle.
+pub type GameState = Board<4, 4>;
+
 /// Creates the default position of tiles, starting with 1 in the top left corner.
-impl Default for GameState {
+impl<const W: usize, const H: usize> Default for Board<W, H> {
     fn default() -> Self {
-        GameState {
-            board: [
-                [Some(1), Some(5), Some(9), Some(13)],
-                [Some(2), Some(6), Some(10), Some(14)],
-                [Some(3), Some(7), Some(11), Some(15)],
-                [Some(4), Some(8), Some(12), None],
-            ],
+        let board = std::array::from_fn(|x| {
+            std::array::from_fn(|y| {
+                let index = y * W + x;
+                (index < Self::MAX_TILE as usize).then_some(index as u8 + 1)
+            })
+        });
+        Board {
+            board,
+            blank: Some(((W - 1) as u8, (H - 1) as u8)),
         }
     }
 }
 
+/// Only the tiles matter, the cached blank position follows from them.
+impl<const W: usize, const H: usize> PartialEq for Board<W, H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.board == other.board
+    }
+}
+
+impl<const W: usize, const H: usize> std::hash::Hash for Board<W, H> {
+    fn hash<S: std::hash::Hasher>(&self, state: &mut S) {
+        self.board.hash(state);
+    }
+}
+
 /// Generates a human-readable representation of the game state.
-impl std::fmt::Display for GameState {
+impl<const W: usize, const H: usize> std::fmt::Display for Board<W, H> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // wide enough for the largest tile, but at least two digits like on the 15-puzzle
+        let width = Self::MAX_TILE.to_string().len().max(2);
         let mut display_state = String::new();
-        for i in 0..4 {
-            display_state.push_str("|");
-            for j in 0..4 {
+        for i in 0..H {
+            display_state.push('|');
+            for j in 0..W {
                 match self.board[j][i] {
-                    Some(val) => display_state.push_str(&format!(" {:>2} |", val)),
-                    None => display_state.push_str(&format!("    |")),
+                    Some(val) => display_state.push_str(&format!(" {:>width$} |", val)),
+                    None => display_state.push_str(&format!(" {:width$} |", "")),
                 }
             }
             display_state.push('\n');
@@ -46,29 +96,35 @@ impl std::fmt::Display for GameState {
     }
 }
 
-/// Checks whether two game states are the same,.
-impl PartialEq for GameState {
-    fn eq(&self, other: &Self) -> bool {
-        for i in 0..4 {
-            //can just compare the arrays directly
-            for j in 0..4 {
-                if self.board[i][j] != other.board[i][j] {
-                    return false;
-                }
-            }
-        }
-        true
-    }
-}
-
 /// Feel free to ignore this. (but do not remove)
-impl Eq for GameState {}
+impl<const W: usize, const H: usize> Eq for Board<W, H> {}
+
+impl<const W: usize, const H: usize> Board<W, H> {
+    /// Largest tile on the board, the tiles have to fit into a `u8`.
+    pub const MAX_TILE: u8 = {
+        assert!(
+            W * H >= 2 && W * H <= 256,
+            "boards need between 2 and 256 cells"
+        );
+        (W * H - 1) as u8
+    };
+
+    /// Creates a state from the tiles indexed by column, then row.
+    pub(crate) fn from_board(board: [[Option<u8>; H]; W]) -> Self {
+        let mut state = Board { board, blank: None };
+        state.blank = state.find_empty_tile();
+        state
+    }
 
-impl GameState {
     /// Updates a position with a new tile.
     pub fn set(&mut self, x: u8, y: u8, tile: Option<u8>) {
-        let (x, y) = (x as usize, y as usize);
-        self.board[x][y] = tile
+        self.board[x as usize][y as usize] = tile;
+        match (tile, self.blank) {
+            (None, None) => self.blank = Some((x, y)),
+            // the tracked blank got filled, there might be another one
+            (Some(_), Some(blank)) if blank == (x, y) => self.blank = self.find_empty_tile(),
+            _ => {}
+        }
     }
 
     /// Returns the tile at position x,y.
@@ -80,34 +136,47 @@ impl GameState {
     /// Returns false if there is a duplicate tile in this game state.
     pub fn all_tiles_unique(&self) -> bool {
         let mut my_set: HashSet<Option<u8>> = HashSet::new();
-        for i in 0..4 {
-            for j in 0..4 {
+        for i in 0..W {
+            for j in 0..H {
                 if my_set.contains(&self.board[i][j]) {
                     return false;
                 }
                 if self.board[i][j].is_some()
-                    && (self.board[i][j].unwrap() > 15 || self.board[i][j].unwrap() < 1)
+                    && (self.board[i][j].unwrap() > Self::MAX_TILE || self.board[i][j].unwrap() < 1)
                 {
                     return false;
                 }
                 my_set.insert(self.board[i][j]);
             }
         }
-        return true;
+        true
     }
 
     /// Swaps the tile from (x1,y1) with the tile from (x2,y2)
     pub fn swap(&mut self, x1: u8, y1: u8, x2: u8, y2: u8) {
+        let (p1, p2) = ((x1, y1), (x2, y2));
         let (x1, y1) = (x1 as usize, y1 as usize);
         let (x2, y2) = (x2 as usize, y2 as usize);
         let temp_tile = self.board[x1][y1];
         self.board[x1][y1] = self.board[x2][y2];
         self.board[x2][y2] = temp_tile;
+        if self.blank == Some(p1) {
+            self.blank = Some(p2);
+        } else if self.blank == Some(p2) {
+            self.blank = Some(p1);
+        }
+    }
+
+    /// Returns the position of the blank, or None if every cell holds a tile.
+    /// If there are several empty cells, this is one of them.
+    pub fn blank(&self) -> Option<(u8, u8)> {
+        self.blank
     }
+
     fn find_empty_tile(&self) -> Option<(u8, u8)> {
-        for x in 0..4 {
-            for y in 0..4 {
-                if self.get(x, y).is_none() {
+        for x in 0..W {
+            for y in 0..H {
+                if self.board[x][y].is_none() {
                     return Some((x as u8, y as u8));
                 }
             }
@@ -117,7 +186,7 @@ impl GameState {
     /// Updates the state to reflect the move that was performed. Returns false if the move was
     /// not possible.
     pub fn perform_move(&mut self, m: Move) -> bool {
-        let Some((col, row)) = self.find_empty_tile() else {
+        let Some((col, row)) = self.blank else {
             return false;
         };
         match m {
@@ -129,7 +198,7 @@ impl GameState {
                 true
             }
             Move::RightToLeft => {
-                if col == 3 {
+                if col as usize == W - 1 {
                     return false;
                 }
                 self.swap(col, row, col + 1, row);
@@ -143,7 +212,7 @@ impl GameState {
                 true
             }
             Move::BottomToTop => {
-                if row == 3 {
+                if row as usize == H - 1 {
                     return false;
                 }
                 self.swap(col, row, col, row + 1);
@@ -152,6 +221,29 @@ impl GameState {
         }
     }
 
+    /// Undoes `m`, which should be the last move performed. Returns false if that is not
+    /// possible.
+    pub fn undo_move(&mut self, m: Move) -> bool {
+        self.perform_move(m.inverse())
+    }
+
+    /// The moves that are possible in this state, in the order of `Move::ALL`.
+    pub fn legal_moves(&self) -> impl Iterator<Item = Move> {
+        let blank = self.blank.map(|(x, y)| y as usize * W + x as usize);
+        Move::ALL.into_iter().filter(move |&m| {
+            blank.is_some_and(|blank| search::move_source::<W, H>(blank, m).is_some())
+        })
+    }
+
+    /// The states one move away, together with the move leading to each.
+    pub fn neighbors(&self) -> impl Iterator<Item = (Move, Self)> + '_ {
+        self.legal_moves().map(|m| {
+            let mut next = self.clone();
+            next.perform_move(m);
+            (m, next)
+        })
+    }
+
     /// Performs a series of moves. Returns the number of moves that were successful.
     pub fn perform_moves(&mut self, moves: &[Move]) -> usize {
         let mut count = 0;
@@ -163,105 +255,333 @@ impl GameState {
         count
     }
 
-    /// Tries to parse a game state from the provided string.
-    /// Returns None if parsing is not possible, or if the parsed game state would contain
-    /// duplicate or invalid tiles.
-    /// Ignores whitespace.
+    /// Returns true if `other` can be reached from this state (and the other way around).
+    /// Moving left or right keeps the order of the tiles, moving up or down moves one tile past
+    /// `W - 1` others. On boards with an odd width this keeps the parity of the number of
+    /// inversions, so two states with the same tiles are connected exactly if it is even. With
+    /// an even width every vertical move flips the parity while changing the row of the blank
+    /// by one, so inversions and blank row distance have to add up to an even number.
+    /// Boards that are a single row or column cannot reorder their tiles at all.
+    /// Returns false if either state has invalid or duplicate tiles.
+    pub fn is_solvable_from(&self, other: &Self) -> bool {
+        // unique tiles between 1 and `MAX_TILE` leave room for exactly one blank, so both
+        // states contain the same tiles
+        if !self.all_tiles_unique() || !other.all_tiles_unique() {
+            return false;
+        }
+        // number the tiles by their position in `other`, which makes `other` free of inversions
+        let mut target_index = vec![0; W * H];
+        for y in 0..H {
+            for x in 0..W {
+                if let Some(tile) = other.board[x][y] {
+                    target_index[tile as usize] = y * W + x;
+                }
+            }
+        }
+        let mut order = Vec::with_capacity(W * H - 1);
+        for y in 0..H {
+            for x in 0..W {
+                if let Some(tile) = self.board[x][y] {
+                    order.push(target_index[tile as usize]);
+                }
+            }
+        }
+        let mut inversions = 0usize;
+        for i in 0..order.len() {
+            for j in i + 1..order.len() {
+                if order[i] > order[j] {
+                    inversions += 1;
+                }
+            }
+        }
+        if W == 1 || H == 1 {
+            return inversions == 0;
+        }
+        if W % 2 == 1 {
+            return inversions.is_multiple_of(2);
+        }
+        let (_, blank_row) = self.blank.unwrap();
+        let (_, other_blank_row) = other.blank.unwrap();
+        (inversions + blank_row.abs_diff(other_blank_row) as usize).is_multiple_of(2)
+    }
+
+    /// Returns true if the default state can be reached from this state.
+    pub fn is_solvable(&self) -> bool {
+        self.is_solvable_from(&Self::default())
+    }
+}
+
+/// Why a game state could not be parsed. Lines and columns count from 1, columns in characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseGameStateError {
+    /// There are `found` rows instead of `expected`. `line` is the first extra or missing line.
+    RowCount {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A row, or the whole board for notations without rows, does not have `expected` cells.
+    CellCount {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A cell holds something that is not a number.
+    NotANumber {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    /// A tile that is not between 1 and `max`.
+    TileOutOfRange {
+        line: usize,
+        column: usize,
+        tile: i64,
+        max: u8,
+    },
+    /// A tile that was already seen on an earlier cell.
+    DuplicateTile {
+        line: usize,
+        column: usize,
+        tile: u8,
+    },
+    /// A second empty cell.
+    SecondBlank { line: usize, column: usize },
+    /// Malformed JSON, `expected` says what should have come at this position.
+    Syntax {
+        line: usize,
+        column: usize,
+        expected: &'static str,
+    },
+}
+
+impl ParseGameStateError {
+    /// The line the error was found on.
+    pub fn line(&self) -> usize {
+        match self {
+            ParseGameStateError::RowCount { line, .. }
+            | ParseGameStateError::CellCount { line, .. }
+            | ParseGameStateError::NotANumber { line, .. }
+            | ParseGameStateError::TileOutOfRange { line, .. }
+            | ParseGameStateError::DuplicateTile { line, .. }
+            | ParseGameStateError::SecondBlank { line, .. }
+            | ParseGameStateError::Syntax { line, .. } => *line,
+        }
+    }
+
+    /// The column of the offending cell, None if the whole line is wrong.
+    pub fn column(&self) -> Option<usize> {
+        match self {
+            ParseGameStateError::RowCount { .. } | ParseGameStateError::CellCount { .. } => None,
+            ParseGameStateError::NotANumber { column, .. }
+            | ParseGameStateError::TileOutOfRange { column, .. }
+            | ParseGameStateError::DuplicateTile { column, .. }
+            | ParseGameStateError::SecondBlank { column, .. }
+            | ParseGameStateError::Syntax { column, .. } => Some(*column),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseGameStateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}", self.line())?;
+        if let Some(column) = self.column() {
+            write!(f, ", column {column}")?;
+        }
+        match self {
+            ParseGameStateError::RowCount {
+                expected, found, ..
+            } => write!(f, ": expected {expected} rows, found {found}"),
+            ParseGameStateError::CellCount {
+                expected, found, ..
+            } => write!(f, ": expected {expected} cells, found {found}"),
+            ParseGameStateError::NotANumber { text, .. } => write!(f, ": '{text}' is not a tile"),
+            ParseGameStateError::TileOutOfRange { tile, max, .. } => {
+                write!(f, ": tile {tile} is not between 1 and {max}")
+            }
+            ParseGameStateError::DuplicateTile { tile, .. } => {
+                write!(f, ": tile {tile} appears twice")
+            }
+            ParseGameStateError::SecondBlank { .. } => write!(f, ": second empty cell"),
+            ParseGameStateError::Syntax { expected, .. } => write!(f, ": expected {expected}"),
+        }
+    }
+}
 
-    pub fn from_str(s: &str) -> Option<Self> {
-        let mut board = [[None; 4]; 4];
-        let mut seen_tiles = HashSet::new();
-        let mut empty_tile_count = 0;
+impl std::error::Error for ParseGameStateError {}
 
-        let rows: Vec<&str> = s.trim().lines().collect();
-        if rows.len() != 4 {
-            return None;
+/// Parses the format written by `Display`. Whitespace around the tiles and empty lines before
+/// and after the board are ignored.
+impl<const W: usize, const H: usize> FromStr for Board<W, H> {
+    type Err = ParseGameStateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<(usize, &str)> = (1..).zip(s.lines()).collect();
+        let is_empty = |(_, line): &&(usize, &str)| line.trim().is_empty();
+        let leading = lines.iter().take_while(is_empty).count();
+        let trailing = lines[leading..].iter().rev().take_while(is_empty).count();
+        let rows = &lines[leading..lines.len() - trailing];
+        if rows.len() != H {
+            let line = match rows.get(H) {
+                Some(&(line, _)) => line,
+                None => rows.last().map_or(1, |&(line, _)| line + 1),
+            };
+            return Err(ParseGameStateError::RowCount {
+                line,
+                expected: H,
+                found: rows.len(),
+            });
         }
 
-        for (i, row) in rows.iter().enumerate() {
+        let mut placer = Placer::new();
+        for &(line, row) in rows {
             let cols: Vec<&str> = row.split('|').collect(); //collect makes a new fieled where it stores changes made on immutable
-            if cols.len() != 6 {
+            if cols.len() != W + 2 {
                 //empty cols at start and end
-                return None;
+                return Err(ParseGameStateError::CellCount {
+                    line,
+                    expected: W,
+                    found: cols.len().saturating_sub(2),
+                });
             }
 
-            for (j, tile) in cols[1..5].iter().enumerate() {
-                let tile = tile.trim();
-                if tile.is_empty() {
-                    board[j][i] = None;
-                    empty_tile_count += 1;
-                    if empty_tile_count > 1 {
-                        return None;
-                    }
-                } else if let Ok(val) = tile.parse::<u8>() {
-                    if val < 1 || val > 15 || !seen_tiles.insert(val) {
-                        //much better than .contains check i orginially did
-                        return None;
-                    }
-                    board[j][i] = Some(val);
+            // byte offset of the current cell in the row
+            let mut offset = cols[0].len() + 1;
+            for cell in &cols[1..W + 1] {
+                let tile = cell.trim();
+                // point at the tile, or at the start of the cell if there is none
+                let start = if tile.is_empty() {
+                    offset
                 } else {
-                    return None;
-                }
+                    offset + cell.len() - cell.trim_start().len()
+                };
+                let column = row[..start].chars().count() + 1;
+                offset += cell.len() + 1;
+                let tile = if tile.is_empty() {
+                    None
+                } else {
+                    match tile.parse::<i64>() {
+                        Ok(val) => Some(val),
+                        Err(_) => {
+                            return Err(ParseGameStateError::NotANumber {
+                                line,
+                                column,
+                                text: tile.to_string(),
+                            })
+                        }
+                    }
+                };
+                placer.place(Cell { line, column, tile })?;
             }
         }
 
-        Some(GameState { board })
+        Ok(placer.finish())
     }
 }
 
+/// Number of states the breadth first searches may remember before they give up.
+pub(crate) const MAX_DEPTH: usize = 10000;
+
 /// Finds the minimal number of moves needed to get from one state to the other.
-/// Might run forever if there is no path, so use with caution!
-pub fn find_shortest_path(from: GameState, to: GameState) -> Vec<Move> {
-    if from == to {
-        return vec![];
+/// Gives up once more than `MAX_DEPTH` states have been seen and reports the state that got
+/// closest to the goal instead.
+pub fn find_shortest_path<const W: usize, const H: usize>(
+    from: Board<W, H>,
+    to: Board<W, H>,
+) -> SolveReport<W, H> {
+    find_shortest_path_to_goal(from, &Goal::from(&to))
+}
+
+/// Same as `find_shortest_path`, but stops at the first state that reaches `goal`.
+pub fn find_shortest_path_to_goal<const W: usize, const H: usize>(
+    from: Board<W, H>,
+    goal: &Goal<W, H>,
+) -> SolveReport<W, H> {
+    if PackedBoard::<W, H>::FITS {
+        breadth_first::<W, H, PackedBoard<W, H>>(from, goal)
+    } else {
+        breadth_first::<W, H, Board<W, H>>(from, goal)
+    }
+}
+
+fn breadth_first<const W: usize, const H: usize, S: SearchState<W, H>>(
+    from: Board<W, H>,
+    goal: &Goal<W, H>,
+) -> SolveReport<W, H> {
+    let start = Instant::now();
+    if let Some(report) = trivial_report(&from, goal, start) {
+        return report;
     }
-    const MAX_DEPTH: usize = 10000;
+    let mut stats = SolveStats::default();
+    // remember the state that is closest to the goal in case we run out of budget
+    let heuristic = Manhattan::for_goal(goal);
+    let from = S::pack(&from);
+    let mut closest = (heuristic.estimate(&from), from.clone());
     //  to store each state with the path of moves to reach it as key val pair
-    let mut possible_states: HashMap<GameState, Vec<Move>> = HashMap::new();
+    let mut possible_states: HashMap<S, Vec<Move>> = HashMap::new();
     let mut queue = VecDeque::new();
     //original state so that it doesn't get store later
     possible_states.insert(from.clone(), vec![]);
     queue.push_back(from);
 
-    while !possible_states.contains_key(&to) {
+    loop {
         if possible_states.len() > MAX_DEPTH {
-            return vec![]; // Return an empty path if the depth limit is exceeded
+            let (distance, closest) = closest;
+            let path = possible_states.remove(&closest).unwrap();
+            let outcome = SolveOutcome::BudgetExhausted {
+                closest: closest.unpack(),
+                path,
+                distance,
+            };
+            stats.elapsed = start.elapsed();
+            return SolveReport { outcome, stats };
         }
         if let Some(current_state) = queue.pop_front() {
             //popping and pushing order doesn't really matter
             let current_path = possible_states.get(&current_state).unwrap().clone();
+            stats.nodes_expanded += 1;
 
-            let moves = [
-                Move::LeftToRight,
-                Move::RightToLeft,
-                Move::TopToBottom,
-                Move::BottomToTop,
-            ];
-            for &m in &moves {
-                let mut new_state = current_state.clone();
+            for m in Move::ALL {
                 //if its valid move
-                if new_state.perform_move(m) {
+                if let Some(new_state) = current_state.apply(m) {
                     // If it isn't already seen
-                    if !possible_states.contains_key(&new_state) {
+                    if let Entry::Vacant(entry) = possible_states.entry(new_state.clone()) {
                         let mut new_path = current_path.clone();
                         new_path.push(m);
-                        possible_states.insert(new_state.clone(), new_path);
+                        entry.insert(new_path);
                         queue.push_back(new_state.clone());
 
-                        if new_state == to {
-                            return possible_states.get(&to).unwrap().clone();
+                        if goal.is_reached_by(&new_state) {
+                            let path = possible_states.remove(&new_state).unwrap();
+                            stats.elapsed = start.elapsed();
+                            return SolveReport {
+                                outcome: SolveOutcome::Solved(path),
+                                stats,
+                            };
+                        }
+                        let distance = heuristic.estimate(&new_state);
+                        if distance < closest.0 {
+                            closest = (distance, new_state);
                         }
                     }
                 }
             }
+            stats.peak_frontier = stats.peak_frontier.max(queue.len());
         } else {
             break;
         }
     }
 
-    vec![]
+    // the queue only runs empty if the goal is unreachable, which the parity check rules out
+    stats.elapsed = start.elapsed();
+    SolveReport {
+        outcome: SolveOutcome::Unsolvable,
+        stats,
+    }
 }
 
+/// Slides the tile next to the blank into it, named after the direction the tile moves in.
+/// Written as `R`, `L`, `D` and `U`, see `Move::letter`.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
 #[repr(u8)]
 pub enum Move {
@@ -271,16 +591,35 @@ pub enum Move {
     BottomToTop,
 }
 
+impl Move {
+    /// All four moves.
+    pub const ALL: [Move; 4] = [
+        Move::LeftToRight,
+        Move::RightToLeft,
+        Move::TopToBottom,
+        Move::BottomToTop,
+    ];
+
+    /// Returns the move that takes the blank back to where it was before `self`.
+    pub fn inverse(self) -> Move {
+        match self {
+            Move::LeftToRight => Move::RightToLeft,
+            Move::RightToLeft => Move::LeftToRight,
+            Move::TopToBottom => Move::BottomToTop,
+            Move::BottomToTop => Move::TopToBottom,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use core::fmt;
-
     use super::*;
     #[test]
     fn tet_size() {
         assert_eq!(1, std::mem::size_of::<u8>());
         assert_eq!(2, std::mem::size_of::<Option<u8>>());
-        assert_eq!(4 * 4 * 2, std::mem::size_of::<GameState>())
+        // the tiles plus the cached blank position
+        assert_eq!(4 * 4 * 2 + 3, std::mem::size_of::<GameState>())
     }
     #[test]
     fn test_default_game_state() {
@@ -315,7 +654,7 @@ mod tests {
         assert_ne!(state.get(0, 2), Some(5));
     }
 
-    const DEFAULT_STATE_STR: &'static str = "\
+    const DEFAULT_STATE_STR: &str = "\
 |  1 |  2 |  3 |  4 |
 |  5 |  6 |  7 |  8 |
 |  9 | 10 | 11 | 12 |
@@ -380,6 +719,38 @@ mod tests {
         assert_eq!(state.get(3, 1), None);
     }
 
+    #[test]
+    fn test_blank_tracking() {
+        let mut state = GameState::default();
+        assert_eq!(state.blank(), Some((3, 3)));
+        state.perform_moves(&[Move::TopToBottom, Move::LeftToRight]);
+        assert_eq!(state.blank(), Some((2, 2)));
+        state.swap(2, 2, 0, 1);
+        assert_eq!(state.blank(), Some((0, 1)));
+        // swapping two tiles leaves the blank where it is
+        state.swap(1, 1, 3, 3);
+        assert_eq!(state.blank(), Some((0, 1)));
+
+        // filling the blank leaves a state without one
+        state.set(0, 1, Some(16));
+        assert_eq!(state.blank(), None);
+        assert!(!state.perform_move(Move::LeftToRight));
+        state.set(2, 0, None);
+        assert_eq!(state.blank(), Some((2, 0)));
+
+        // with two empty cells, filling one of them moves on to the other
+        state.set(1, 3, None);
+        state.set(2, 0, Some(3));
+        assert_eq!(state.blank(), Some((1, 3)));
+        assert!(state.perform_move(Move::TopToBottom));
+        assert_eq!(state.blank(), Some((1, 2)));
+
+        let mut state = GameState::default();
+        state.perform_moves(&[Move::TopToBottom, Move::TopToBottom]);
+        let parsed = GameState::from_str(&format!("{state}")).unwrap();
+        assert_eq!(parsed.blank(), Some((3, 1)));
+    }
+
     #[test]
     fn test_game_state_equality() {
         let mut state = GameState::default();
@@ -396,6 +767,37 @@ mod tests {
         assert_eq!(state, state_2);
     }
 
+    #[test]
+    fn test_legal_moves() {
+        let mut state = GameState::default();
+        let legal: Vec<Move> = state.legal_moves().collect();
+        assert_eq!(legal, vec![Move::LeftToRight, Move::TopToBottom]);
+        state.perform_moves(&[Move::TopToBottom, Move::LeftToRight]);
+        assert_eq!(state.legal_moves().collect::<Vec<_>>(), Move::ALL);
+
+        for (m, next) in state.neighbors() {
+            let mut expected = state.clone();
+            assert!(expected.perform_move(m));
+            assert_eq!(next, expected);
+            let mut back = next.clone();
+            assert!(back.undo_move(m));
+            assert_eq!(back, state);
+        }
+        assert_eq!(state.neighbors().count(), 4);
+
+        let mut corner = Board::<2, 2>::default();
+        corner.perform_moves(&[Move::LeftToRight, Move::TopToBottom]);
+        let moves: Vec<Move> = corner.neighbors().map(|(m, _)| m).collect();
+        assert_eq!(moves, vec![Move::RightToLeft, Move::BottomToTop]);
+
+        let mut no_blank = GameState::default();
+        no_blank.set(3, 3, Some(16));
+        assert_eq!(no_blank.legal_moves().count(), 0);
+        assert!(!GameState::default().undo_move(Move::LeftToRight));
+        assert_eq!(Move::LeftToRight.inverse(), Move::RightToLeft);
+        assert!(Move::ALL.iter().all(|m| m.inverse().inverse() == *m));
+    }
+
     #[test]
     fn test_perform_moves() {
         let mut state = GameState::default();
@@ -498,14 +900,201 @@ mod tests {
 | 13 | 14 | 15 | 12 |
 ";
 
-        assert!(GameState::from_str(wrong0).is_none());
-        assert!(GameState::from_str(wrong1).is_none());
-        assert!(GameState::from_str(wrong2).is_none());
-        assert!(GameState::from_str(wrong3).is_none());
-        assert!(GameState::from_str(wrong4).is_none());
-        assert!(GameState::from_str(wrong5).is_none());
-        assert!(GameState::from_str(wrong6).is_none()); //should still be wrong since none should be unique too...
-        assert!(GameState::from_str(wrong7).is_none());
+        assert!(GameState::from_str(wrong0).is_err());
+        assert!(GameState::from_str(wrong1).is_err());
+        assert!(GameState::from_str(wrong2).is_err());
+        assert!(GameState::from_str(wrong3).is_err());
+        assert!(GameState::from_str(wrong4).is_err());
+        assert!(GameState::from_str(wrong5).is_err());
+        assert!(GameState::from_str(wrong6).is_err()); //should still be wrong since none should be unique too...
+        assert!(GameState::from_str(wrong7).is_err());
+
+        // the errors point at the problem
+        assert_eq!(
+            wrong0.parse::<GameState>(),
+            Err(ParseGameStateError::TileOutOfRange {
+                line: 1,
+                column: 8,
+                tile: 22,
+                max: 15
+            })
+        );
+        let cell_count = |found| ParseGameStateError::CellCount {
+            line: 1,
+            expected: 4,
+            found,
+        };
+        assert_eq!(wrong1.parse::<GameState>(), Err(cell_count(3)));
+        assert_eq!(wrong2.parse::<GameState>(), Err(cell_count(3)));
+        assert_eq!(wrong4.parse::<GameState>(), Err(cell_count(5)));
+        assert_eq!(
+            wrong3.parse::<GameState>(),
+            Err(ParseGameStateError::RowCount {
+                line: 5,
+                expected: 4,
+                found: 5
+            })
+        );
+        assert_eq!(
+            wrong5.parse::<GameState>(),
+            Err(ParseGameStateError::DuplicateTile {
+                line: 2,
+                column: 9,
+                tile: 2
+            })
+        );
+        assert_eq!(
+            wrong6.parse::<GameState>(),
+            Err(ParseGameStateError::SecondBlank { line: 1, column: 7 })
+        );
+        let error = wrong7.parse::<GameState>().unwrap_err();
+        assert_eq!((error.line(), error.column()), (1, Some(13)));
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 13: tile 16 is not between 1 and 15"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        // leading empty lines still count
+        let top_rows = "\n\n|  1 |  2 |  3 |    |\n|  5 | ab |  7 |  4 |\n";
+        let error = top_rows.parse::<GameState>().unwrap_err();
+        assert_eq!(
+            error,
+            ParseGameStateError::RowCount {
+                line: 5,
+                expected: 4,
+                found: 2
+            }
+        );
+        assert_eq!(error.to_string(), "line 5: expected 4 rows, found 2");
+        let error = format!("{top_rows}|  9 | 10 | 11 |  8 |\n| 13 | 14 | 15 | 12 |\n\n")
+            .parse::<GameState>()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            ParseGameStateError::NotANumber {
+                line: 4,
+                column: 8,
+                text: "ab".to_string()
+            }
+        );
+        assert_eq!(error.to_string(), "line 4, column 8: 'ab' is not a tile");
+        assert_eq!(
+            "".parse::<GameState>(),
+            Err(ParseGameStateError::RowCount {
+                line: 1,
+                expected: 4,
+                found: 0
+            })
+        );
+        let negative = DEFAULT_STATE_STR.replace("13", "-1");
+        let error = negative.parse::<GameState>().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 4, column 3: tile -1 is not between 1 and 15"
+        );
+    }
+
+    #[test]
+    fn test_parse_error_composes() -> Result<(), Box<dyn std::error::Error>> {
+        let state: GameState = DEFAULT_STATE_STR.parse()?;
+        assert_eq!(state, GameState::default());
+        let result: Result<GameState, Box<dyn std::error::Error>> =
+            "|  1 |".parse::<GameState>().map_err(Into::into);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_solvable() {
+        let mut state = GameState::default();
+        assert!(state.is_solvable());
+        assert!(state.is_solvable_from(&state));
+
+        // sliding tiles around never changes solvability
+        state.perform_moves(&[
+            Move::TopToBottom,
+            Move::LeftToRight,
+            Move::TopToBottom,
+            Move::LeftToRight,
+            Move::BottomToTop,
+        ]);
+        assert!(state.is_solvable());
+        assert!(GameState::default().is_solvable_from(&state));
+
+        // Sam Loyd's famous 14-15 puzzle
+        let mut swapped = GameState::default();
+        swapped.swap(1, 3, 2, 3);
+        assert!(!swapped.is_solvable());
+        assert!(!state.is_solvable_from(&swapped));
+        // two swaps cancel each other out
+        swapped.swap(0, 0, 1, 0);
+        assert!(swapped.is_solvable());
+
+        // swapping the blank with its neighbour is a move, jumping over a tile is not
+        let mut blank_up = GameState::default();
+        blank_up.swap(3, 3, 3, 2);
+        assert!(blank_up.is_solvable());
+        let mut blank_jump = GameState::default();
+        blank_jump.swap(3, 3, 3, 1);
+        assert!(!blank_jump.is_solvable());
+        let mut blank_jump = GameState::default();
+        blank_jump.swap(3, 3, 1, 3);
+        assert!(!blank_jump.is_solvable());
+
+        let mut invalid = GameState::default();
+        invalid.set(0, 0, Some(16));
+        assert!(!invalid.is_solvable());
+        invalid.set(0, 0, Some(2));
+        assert!(!invalid.is_solvable());
+        assert!(!GameState::default().is_solvable_from(&invalid));
+    }
+
+    #[test]
+    fn test_other_sizes() {
+        let mut eight = Board::<3, 3>::default();
+        let expected = "\
+|  1 |  2 |  3 |
+|  4 |  5 |  6 |
+|  7 |  8 |    |
+";
+        assert_eq!(expected, format!("{eight}"));
+        assert_eq!(Board::<3, 3>::from_str(expected), Ok(eight.clone()));
+        assert!(GameState::from_str(expected).is_err());
+        assert!(!eight.perform_move(Move::RightToLeft));
+        assert!(!eight.perform_move(Move::BottomToTop));
+        assert!(eight.perform_move(Move::TopToBottom));
+        assert_eq!(eight.blank(), Some((2, 1)));
+        assert!(eight.is_solvable());
+        let mut swapped = Board::<3, 3>::default();
+        swapped.swap(0, 0, 1, 0);
+        assert!(!swapped.is_solvable());
+        // on odd widths a vertical move keeps the parity, so the blank row does not matter
+        swapped.swap(2, 2, 2, 0);
+        assert!(swapped.is_solvable());
+
+        let rectangle = Board::<2, 4>::default();
+        let expected = "\
+|  1 |  2 |
+|  3 |  4 |
+|  5 |  6 |
+|  7 |    |
+";
+        assert_eq!(expected, format!("{rectangle}"));
+        assert_eq!(Board::<2, 4>::from_str(expected), Ok(rectangle));
+        assert!(Board::<4, 2>::from_str(expected).is_err());
+        // 8 is not a tile of the 2x4 board
+        assert!(Board::<2, 4>::from_str(&expected.replace('7', "8")).is_err());
+
+        // the cells grow once the tiles need three digits
+        let big = Board::<11, 10>::default();
+        assert_eq!(Board::<11, 10>::MAX_TILE, 109);
+        let text = format!("{big}");
+        assert!(text.starts_with("|   1 |   2 |"));
+        assert!(text.ends_with("| 108 | 109 |     |\n"));
+        assert_eq!(Board::<11, 10>::from_str(&text), Ok(big));
     }
 
     #[test]
@@ -514,7 +1103,9 @@ mod tests {
         let mut state = GameState::default();
         assert_eq!(state.perform_moves(&expected_moves), 3);
 
-        let actual_moves = find_shortest_path(GameState::default(), state);
+        let actual_moves = find_shortest_path(GameState::default(), state)
+            .into_path()
+            .unwrap();
         assert_eq!(actual_moves.len(), 3);
         assert_eq!(actual_moves, expected_moves);
 
@@ -533,18 +1124,29 @@ mod tests {
             Move::TopToBottom,
         ];
         assert_eq!(state2.perform_moves(&expected_moves2), 8);
-        let actual_moves = find_shortest_path(GameState::default(), state2);
+        let actual_moves = find_shortest_path(GameState::default(), state2)
+            .into_path()
+            .unwrap();
         assert_eq!(actual_moves.len(), 4);
 
         // this test is for finding path to the default state
         let state = GameState::default();
-        let moves = find_shortest_path(state.clone(), state.clone());
+        let moves = find_shortest_path(state.clone(), state.clone())
+            .into_path()
+            .unwrap();
         assert_eq!(moves.len(), 0);
 
         let mut state_invalid = GameState::default();
         state_invalid.set(0, 0, Some(16)); // Set an invalid tile so its impossible
-        let moves = find_shortest_path(GameState::default(), state_invalid);
-        assert!(moves.is_empty());
+        let report = find_shortest_path(GameState::default(), state_invalid);
+        assert_eq!(report.outcome, SolveOutcome::Unsolvable);
+
+        // swapping two tiles can never be undone by sliding
+        let mut state_unsolvable = GameState::default();
+        state_unsolvable.swap(1, 3, 2, 3);
+        let report = find_shortest_path(GameState::default(), state_unsolvable);
+        assert_eq!(report.outcome, SolveOutcome::Unsolvable);
+        assert_eq!(report.stats.nodes_expanded, 0);
 
         let mut state3 = GameState::default();
         let expected_moves3 = [
@@ -554,8 +1156,64 @@ mod tests {
             Move::RightToLeft,
         ];
         assert_eq!(state3.perform_moves(&expected_moves3), 4);
-        let actual_moves3 = find_shortest_path(GameState::default(), state3);
+        let actual_moves3 = find_shortest_path(GameState::default(), state3)
+            .into_path()
+            .unwrap();
         assert_eq!(actual_moves3.len(), 4);
         assert_eq!(actual_moves3, expected_moves3);
     }
+
+    #[test]
+    fn test_find_shortest_path_report() {
+        let mut state = GameState::default();
+        let moves = [Move::TopToBottom, Move::LeftToRight, Move::TopToBottom];
+        assert_eq!(state.perform_moves(&moves), 3);
+        let report = find_shortest_path(GameState::default(), state.clone());
+        assert!(report.is_solved());
+        assert_eq!(report.path(), Some(&moves[..]));
+        assert!(report.stats.nodes_expanded > 0);
+        assert!(report.stats.peak_frontier > 0);
+
+        // too deep for the breadth first search, it still tells us how far it got
+        let moves = [
+            Move::TopToBottom,
+            Move::TopToBottom,
+            Move::TopToBottom,
+            Move::LeftToRight,
+            Move::LeftToRight,
+            Move::LeftToRight,
+            Move::BottomToTop,
+            Move::BottomToTop,
+            Move::BottomToTop,
+            Move::RightToLeft,
+            Move::RightToLeft,
+            Move::TopToBottom,
+            Move::TopToBottom,
+            Move::LeftToRight,
+            Move::BottomToTop,
+            Move::RightToLeft,
+            Move::TopToBottom,
+            Move::LeftToRight,
+            Move::TopToBottom,
+            Move::RightToLeft,
+        ];
+        let mut deep = GameState::default();
+        assert_eq!(deep.perform_moves(&moves), moves.len());
+        let report = find_shortest_path(GameState::default(), deep.clone());
+        assert!(!report.is_solved());
+        assert_eq!(report.path(), None);
+        let SolveOutcome::BudgetExhausted {
+            closest,
+            path,
+            distance,
+        } = report.outcome
+        else {
+            panic!("expected the search to run out of budget");
+        };
+        let mut reached = GameState::default();
+        assert_eq!(reached.perform_moves(&path), path.len());
+        assert_eq!(reached, closest);
+        assert_eq!(distance, Manhattan::new(&deep).estimate(&closest));
+        assert!(distance < Manhattan::new(&deep).estimate(&GameState::default()));
+    }
 }
@@ -0,0 +1,627 @@
+use puzzle15::{
+    find_shortest_path_idastar_budget, play, Board, Ending, Game, Keys, Move, Notation,
+    ParseGameStateError, SolveOutcome,
+};
+use std::io::{IsTerminal, Read};
+use std::process::{ExitCode, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "\
+Usage: puzzle15 <COMMAND> [OPTIONS] [FILE]
+
+Commands:
+  solve [FILE]         print the moves that solve the board
+  distance [FILE]      print the number of moves needed to solve the board
+  validate [FILE]      check that the board is valid and solvable
+  apply MOVES [FILE]   perform moves like \"DRRU\" or \"DR2U\" on the board and print it
+  scramble             print a random solvable board
+  play [FILE]          play the board from FILE, or a scramble if there is none
+
+Boards are read from FILE, or from stdin if it is missing or \"-\".
+
+Options:
+  --json               print machine-readable output
+  --notation NAME      grid, flat, compact or json, detected when reading and grid when
+                       writing if not given
+  --size WxH           board size, one of 3x3, 4x4 or 5x5, defaults to 4x4
+  --seed N             seed of the scramble, defaults to 0, or the current time for play
+  --moves N            scramble with a random walk of N moves instead of picking a state
+  --max-nodes N        give up solving after expanding N states, defaults to 20000000
+  -h, --help           print this help
+";
+
+/// Keeps `solve` and `distance` from running for hours on boards that are too far from solved,
+/// which happens easily on 5x5 boards.
+const DEFAULT_MAX_NODES: u64 = 20_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Solve,
+    Distance,
+    Validate,
+    Apply,
+    Scramble,
+    Play,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Options {
+    command: Command,
+    json: bool,
+    notation: Option<Notation>,
+    size: (usize, usize),
+    seed: Option<u64>,
+    moves: Option<usize>,
+    max_nodes: u64,
+    /// the move string given to `apply`
+    apply: Option<String>,
+    file: Option<String>,
+}
+
+/// What the command line asked for, `Help` if that is all.
+#[derive(Debug, PartialEq, Eq)]
+enum Invocation {
+    Help,
+    Run(Options),
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Invocation, String> {
+    let mut args = args.into_iter();
+    let command = match args.next().as_deref() {
+        None | Some("-h" | "--help" | "help") => return Ok(Invocation::Help),
+        Some("solve") => Command::Solve,
+        Some("distance") => Command::Distance,
+        Some("validate") => Command::Validate,
+        Some("apply") => Command::Apply,
+        Some("scramble") => Command::Scramble,
+        Some("play") => Command::Play,
+        Some(other) => return Err(format!("unknown command '{other}'")),
+    };
+    let mut options = Options {
+        command,
+        json: false,
+        notation: None,
+        size: (4, 4),
+        seed: None,
+        moves: None,
+        max_nodes: DEFAULT_MAX_NODES,
+        apply: None,
+        file: None,
+    };
+    let mut positional = vec![];
+    while let Some(arg) = args.next() {
+        // both "--seed 5" and "--seed=5"
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || match inline {
+            Some(value) => Ok(value.to_string()),
+            None => args.next().ok_or(format!("{name} needs a value")),
+        };
+        match name.as_str() {
+            "-h" | "--help" => return Ok(Invocation::Help),
+            "--json" => options.json = true,
+            "--notation" => options.notation = Some(parse_notation(&value()?)?),
+            "--size" => options.size = parse_size(&value()?)?,
+            "--seed" => options.seed = Some(parse_number(&name, &value()?)?),
+            "--moves" => options.moves = Some(parse_number(&name, &value()?)?),
+            "--max-nodes" => options.max_nodes = parse_number(&name, &value()?)?,
+            _ if name.starts_with("--") => return Err(format!("unknown option '{name}'")),
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    if command == Command::Apply {
+        options.apply = Some(
+            positional
+                .next()
+                .ok_or("apply needs the moves to perform")?,
+        );
+    }
+    if command != Command::Scramble {
+        options.file = positional.next().filter(|file| file != "-");
+    }
+    if let Some(extra) = positional.next() {
+        return Err(format!("unexpected argument '{extra}'"));
+    }
+    Ok(Invocation::Run(options))
+}
+
+fn parse_notation(name: &str) -> Result<Notation, String> {
+    match name {
+        "grid" => Ok(Notation::Grid),
+        "flat" => Ok(Notation::Flat),
+        "compact" => Ok(Notation::Compact),
+        "json" => Ok(Notation::Json),
+        _ => Err(format!("unknown notation '{name}'")),
+    }
+}
+
+/// The board size is a compile time parameter of the library, `main` picks from the sizes listed
+/// here.
+fn parse_size(size: &str) -> Result<(usize, usize), String> {
+    match size {
+        "3x3" => Ok((3, 3)),
+        "4x4" => Ok((4, 4)),
+        "5x5" => Ok((5, 5)),
+        _ => Err(format!(
+            "unsupported size '{size}', the supported sizes are 3x3, 4x4 and 5x5"
+        )),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{name} needs a number, got '{value}'"))
+}
+
+/// Writes `text` as a JSON string.
+fn json_string(text: &str) -> String {
+    let mut json = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Runs the command on a `W` x `H` board. `read` gets the file name, None for stdin.
+/// Returns the text to print, or the reason the command failed.
+fn run<const W: usize, const H: usize>(
+    options: &Options,
+    read: impl FnOnce(Option<&str>) -> Result<String, String>,
+) -> Result<String, String> {
+    let format = |board: &Board<W, H>| -> Result<String, String> {
+        if options.json {
+            return Ok(board.to_notation(Notation::Json).unwrap());
+        }
+        let notation = options.notation.unwrap_or(Notation::Grid);
+        board
+            .to_notation(notation)
+            .ok_or_else(|| "the tiles are too large for the compact notation".to_string())
+    };
+
+    if options.command == Command::Scramble {
+        let seed = options.seed.unwrap_or(0);
+        let (board, moves) = scramble::<W, H>(options, seed);
+        if !options.json {
+            return format(&board);
+        }
+        let moves = match moves {
+            Some(moves) => format!(",\"moves\":{}", json_string(&Move::format_sequence(&moves))),
+            None => String::new(),
+        };
+        return Ok(format!(
+            "{{\"board\":{},\"seed\":{seed}{moves}}}",
+            format(&board)?
+        ));
+    }
+    if options.command == Command::Play {
+        let start = match &options.file {
+            Some(file) => parse_board(options, &read(Some(file))?)
+                .map_err(|error| format!("invalid board: {error}"))?,
+            None => {
+                // a different game every time unless asked for a certain one
+                let now = SystemTime::now().duration_since(UNIX_EPOCH);
+                let seed = options
+                    .seed
+                    .unwrap_or_else(|| now.map_or(0, |now| now.as_nanos() as u64));
+                scramble::<W, H>(options, seed).0
+            }
+        };
+        return play_on_terminal(start, options.json);
+    }
+
+    let text = read(options.file.as_deref())?;
+    let parsed = parse_board::<W, H>(options, &text);
+    if options.command == Command::Validate {
+        // the answer goes to the same place either way, but only a solvable board succeeds
+        return match (parsed, options.json) {
+            (Ok(board), false) if board.is_solvable() => Ok("valid, solvable".to_string()),
+            (Ok(_), false) => Err("valid, but not solvable".to_string()),
+            (Err(error), false) => Err(format!("invalid: {error}")),
+            (Ok(board), true) if board.is_solvable() => {
+                Ok("{\"valid\":true,\"solvable\":true}".to_string())
+            }
+            (Ok(_), true) => Err("{\"valid\":true,\"solvable\":false}".to_string()),
+            (Err(error), true) => Err(format!(
+                "{{\"valid\":false,\"error\":{}}}",
+                json_string(&error.to_string())
+            )),
+        };
+    }
+    let mut board = parsed.map_err(|error| format!("invalid board: {error}"))?;
+
+    match options.command {
+        Command::Apply => {
+            let moves = Move::parse_sequence(options.apply.as_deref().unwrap_or_default())
+                .map_err(|error| format!("invalid moves: {error}"))?;
+            for (i, &m) in moves.iter().enumerate() {
+                if !board.perform_move(m) {
+                    return Err(format!("move {} ({m}) is not possible", i + 1));
+                }
+            }
+            if !options.json {
+                return format(&board);
+            }
+            let solved = board == Board::default();
+            Ok(format!(
+                "{{\"board\":{},\"solved\":{solved}}}",
+                format(&board)?
+            ))
+        }
+        Command::Solve | Command::Distance => {
+            let report =
+                find_shortest_path_idastar_budget(board, Board::default(), options.max_nodes);
+            let path = match report.outcome {
+                SolveOutcome::Solved(path) => path,
+                SolveOutcome::BudgetExhausted { .. } | SolveOutcome::NoPathWithin(_) => {
+                    let nodes = report.stats.nodes_expanded;
+                    return Err(format!("gave up after {nodes} nodes"));
+                }
+                SolveOutcome::Unsolvable => return Err("the board is not solvable".to_string()),
+            };
+            let moves = Move::format_sequence(&path);
+            Ok(match (options.command, options.json) {
+                (Command::Solve, false) => moves,
+                (Command::Solve, true) => format!(
+                    "{{\"moves\":{},\"length\":{}}}",
+                    json_string(&moves),
+                    path.len()
+                ),
+                (_, false) => path.len().to_string(),
+                (_, true) => format!("{{\"distance\":{}}}", path.len()),
+            })
+        }
+        Command::Validate | Command::Scramble | Command::Play => unreachable!("handled above"),
+    }
+}
+
+/// A random walk of `--moves` moves, or a random solvable board if that is not given.
+fn scramble<const W: usize, const H: usize>(
+    options: &Options,
+    seed: u64,
+) -> (Board<W, H>, Option<Vec<Move>>) {
+    match options.moves {
+        Some(len) => {
+            let (board, moves) = Board::random_walk(len, seed);
+            (board, Some(moves))
+        }
+        None => (Board::random_solvable(seed), None),
+    }
+}
+
+/// Reads a board in the `--notation`, or whatever notation it looks like.
+fn parse_board<const W: usize, const H: usize>(
+    options: &Options,
+    text: &str,
+) -> Result<Board<W, H>, ParseGameStateError> {
+    match options.notation {
+        Some(notation) => Board::parse_notation(text, notation),
+        None => Board::parse_any(text),
+    }
+}
+
+/// Plays on the terminal until the board is solved, the player quits or stdin is closed.
+fn play_on_terminal<const W: usize, const H: usize>(
+    start: Board<W, H>,
+    json: bool,
+) -> Result<String, String> {
+    let mut game = Game::new(start);
+    let ending = {
+        let _raw = RawTerminal::enable();
+        play(
+            &mut game,
+            Keys::new(std::io::stdin()),
+            &mut std::io::stdout(),
+        )
+        .map_err(|error| format!("cannot write to stdout: {error}"))?
+    };
+    let (ending, moves) = match ending {
+        Ending::Solved { moves } => ("solved", moves),
+        Ending::Quit | Ending::InputClosed => ("quit", game.moves()),
+    };
+    Ok(match json {
+        false if ending == "solved" => String::new(),
+        false => format!("quit after {moves} moves"),
+        true => format!("{{\"ending\":\"{ending}\",\"moves\":{moves}}}"),
+    })
+}
+
+/// Lets the game see every key as soon as it is pressed, without echoing it, for as long as it
+/// lives. Does nothing if stdin is not a terminal. Ctrl-C reaches the game as a key instead of
+/// killing the process, so the terminal is set back on the way out, and a panic sets it back too.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enable() -> Option<RawTerminal> {
+        if !std::io::stdin().is_terminal() {
+            return None;
+        }
+        let status = std::process::Command::new("stty")
+            .args(["-icanon", "-echo", "-isig"])
+            .stdin(Stdio::inherit())
+            .status();
+        if !status.is_ok_and(|status| status.success()) {
+            return None;
+        }
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            RawTerminal::restore();
+            hook(info);
+        }));
+        Some(RawTerminal)
+    }
+
+    fn restore() {
+        let _ = std::process::Command::new("stty")
+            .args(["icanon", "echo", "isig"])
+            .stdin(Stdio::inherit())
+            .status();
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        RawTerminal::restore();
+    }
+}
+
+fn read_input(file: Option<&str>) -> Result<String, String> {
+    match file {
+        Some(file) => {
+            std::fs::read_to_string(file).map_err(|error| format!("cannot read {file}: {error}"))
+        }
+        None => {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|error| format!("cannot read stdin: {error}"))?;
+            Ok(text)
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Invocation::Help) => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Ok(Invocation::Run(options)) => options,
+        Err(error) => {
+            eprintln!("error: {error}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let result = match options.size {
+        (3, 3) => run::<3, 3>(&options, read_input),
+        (5, 5) => run::<5, 5>(&options, read_input),
+        _ => run::<4, 4>(&options, read_input),
+    };
+    match result {
+        Ok(output) => {
+            println!("{}", output.trim_end());
+            ExitCode::SUCCESS
+        }
+        // machine-readable failures still go to stdout
+        Err(output) if options.json && output.starts_with('{') => {
+            println!("{output}");
+            ExitCode::FAILURE
+        }
+        Err(error) if options.json => {
+            println!("{{\"error\":{}}}", json_string(&error));
+            ExitCode::FAILURE
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use puzzle15::GameState;
+
+    fn args(line: &str) -> Result<Options, String> {
+        match parse_args(line.split_whitespace().map(str::to_string))? {
+            Invocation::Run(options) => Ok(options),
+            Invocation::Help => Err("help".to_string()),
+        }
+    }
+
+    /// Runs the command line on a 4x4 board with `input` as the file contents.
+    fn run_with(line: &str, input: &str) -> Result<String, String> {
+        run::<4, 4>(&args(line)?, |_| Ok(input.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = args("scramble --seed 7 --moves=20 --json").unwrap();
+        assert_eq!(options.command, Command::Scramble);
+        assert_eq!(
+            (options.seed, options.moves, options.json),
+            (Some(7), Some(20), true)
+        );
+        let options = args("apply DR2U board.txt --notation flat").unwrap();
+        assert_eq!(options.apply.as_deref(), Some("DR2U"));
+        assert_eq!(options.file.as_deref(), Some("board.txt"));
+        assert_eq!(options.notation, Some(Notation::Flat));
+        assert_eq!(args("solve -").unwrap().file, None);
+        assert_eq!(args("distance --size 3x3").unwrap().size, (3, 3));
+        assert_eq!(args("solve").unwrap().max_nodes, DEFAULT_MAX_NODES);
+        assert_eq!(args("solve --max-nodes 500").unwrap().max_nodes, 500);
+        let options = args("play --seed 5").unwrap();
+        assert_eq!((options.command, options.seed), (Command::Play, Some(5)));
+
+        assert_eq!(parse_args([]), Ok(Invocation::Help));
+        assert_eq!(
+            parse_args(["solve".into(), "-h".into()]),
+            Ok(Invocation::Help)
+        );
+        assert_eq!(
+            args("shuffle"),
+            Err("unknown command 'shuffle'".to_string())
+        );
+        assert_eq!(
+            args("solve --fast"),
+            Err("unknown option '--fast'".to_string())
+        );
+        assert_eq!(
+            args("scramble --seed"),
+            Err("--seed needs a value".to_string())
+        );
+        assert_eq!(
+            args("scramble --seed x"),
+            Err("--seed needs a number, got 'x'".to_string())
+        );
+        assert_eq!(
+            args("solve a b"),
+            Err("unexpected argument 'b'".to_string())
+        );
+        assert_eq!(
+            args("apply"),
+            Err("apply needs the moves to perform".to_string())
+        );
+        assert_eq!(
+            args("solve --size 9x2"),
+            Err("unsupported size '9x2', the supported sizes are 3x3, 4x4 and 5x5".to_string())
+        );
+    }
+
+    #[test]
+    fn test_solve() {
+        let mut state = GameState::default();
+        state.perform_moves(&Move::parse_sequence("DRDL").unwrap());
+        let input = state.to_string();
+        assert_eq!(run_with("solve", &input), Ok("RULU".to_string()));
+        assert_eq!(
+            run_with("solve --json", &input),
+            Ok(r#"{"moves":"RULU","length":4}"#.to_string())
+        );
+        assert_eq!(run_with("distance", &input), Ok("4".to_string()));
+        let flat = state.to_notation(Notation::Flat).unwrap();
+        assert_eq!(
+            run_with("distance --json", &flat),
+            Ok(r#"{"distance":4}"#.to_string())
+        );
+        assert_eq!(run_with("solve", "123456789ABCDEF0"), Ok(String::new()));
+
+        let mut swapped = GameState::default();
+        swapped.swap(0, 0, 1, 0);
+        assert_eq!(
+            run_with("solve", &swapped.to_string()),
+            Err("the board is not solvable".to_string())
+        );
+    }
+
+    #[test]
+    fn test_solve_budget() {
+        // far enough from solved that a 5x5 search would run for a long time
+        let scrambled = run::<5, 5>(&args("scramble --size 5x5 --seed 1").unwrap(), |_| {
+            Ok(String::new())
+        })
+        .unwrap();
+        for command in ["solve", "distance --json"] {
+            let line = format!("{command} --size 5x5 --max-nodes 1000");
+            assert_eq!(
+                run::<5, 5>(&args(&line).unwrap(), |_| Ok(scrambled.clone())),
+                Err("gave up after 1000 nodes".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let compact = "123456789ABCDEF0";
+        assert_eq!(
+            run_with("validate", compact),
+            Ok("valid, solvable".to_string())
+        );
+        assert_eq!(
+            run_with("validate --json", compact),
+            Ok(r#"{"valid":true,"solvable":true}"#.to_string())
+        );
+        assert_eq!(
+            run_with("validate", "213456789ABCDEF0"),
+            Err("valid, but not solvable".to_string())
+        );
+        assert_eq!(
+            run_with("validate --json", "113456789ABCDEF0"),
+            Err(r#"{"valid":false,"error":"line 1, column 2: tile 1 appears twice"}"#.to_string())
+        );
+        // the notation can be forced
+        assert_eq!(
+            run_with("validate --notation flat", compact),
+            Err("invalid: line 1: expected 16 cells, found 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let default = GameState::default().to_string();
+        let mut expected = GameState::default();
+        expected.perform_moves(&Move::parse_sequence("RRD").unwrap());
+        assert_eq!(run_with("apply R2D", &default), Ok(expected.to_string()));
+        assert_eq!(
+            run_with("apply R2D --notation compact", "123456789ABCDEF0"),
+            Ok("1234567890BCDAEF".to_string())
+        );
+        assert_eq!(
+            run_with("apply RL --json", &default),
+            Ok(
+                r#"{"board":[[1,2,3,4],[5,6,7,8],[9,10,11,12],[13,14,15,0]],"solved":true}"#
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            run_with("apply RL", "1 2 3"),
+            Err("invalid board: line 1: expected 16 cells, found 3".to_string())
+        );
+        assert_eq!(
+            run_with("apply RU", &default),
+            Err("move 2 (U) is not possible".to_string())
+        );
+        assert_eq!(
+            run_with("apply RX", &default),
+            Err("invalid moves: column 2: 'X' is not a move".to_string())
+        );
+    }
+
+    #[test]
+    fn test_scramble() {
+        let walk = run_with("scramble --seed 3 --moves 10 --json", "").unwrap();
+        let (_, moves) = GameState::random_walk(10, 3);
+        assert!(walk.ends_with(&format!(
+            r#","seed":3,"moves":"{}"}}"#,
+            Move::format_sequence(&moves)
+        )));
+        let board: GameState = run_with("scramble --seed 3", "").unwrap().parse().unwrap();
+        assert_eq!(board, GameState::random_solvable(3));
+        assert_eq!(
+            run::<3, 3>(
+                &args("scramble --notation compact").unwrap(),
+                |_| unreachable!()
+            ),
+            Ok(Board::<3, 3>::random_solvable(0)
+                .to_notation(Notation::Compact)
+                .unwrap())
+        );
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), r#""a\"b\\c\nd\u0001""#);
+    }
+}
@@ -0,0 +1,811 @@
+use crate::{Board, Move, ParseGameStateError};
+use std::str::FromStr;
+
+/// The text formats boards are exchanged in. All of them list the tiles row by row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Notation {
+    /// The grid of `|` separated cells written by `Display` and read by `FromStr`.
+    Grid,
+    /// The tiles separated by whitespace or commas with 0 for the blank,
+    /// `"1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 0"`.
+    Flat,
+    /// One character per tile with 0 for the blank, `"123456789ABCDEF0"`. Tiles above 9 are
+    /// written as letters, so this works for boards of up to 36 cells.
+    Compact,
+    /// A JSON array of rows with 0 for the blank, `[[1,2,3,4],[5,6,7,8],...]`. A flat array of
+    /// all tiles is read as well, and so is `null` for the blank.
+    Json,
+}
+
+impl Notation {
+    /// Guesses the notation of `s`: JSON arrays start with `[`, grids contain `|`, a single word
+    /// is compact and everything else is read as a flat list.
+    pub fn detect(s: &str) -> Notation {
+        let s = s.trim();
+        if s.starts_with('[') {
+            Notation::Json
+        } else if s.contains('|') {
+            Notation::Grid
+        } else if !s.contains(|c: char| c.is_whitespace() || c == ',') {
+            Notation::Compact
+        } else {
+            Notation::Flat
+        }
+    }
+}
+
+/// A tile read from the input together with where it starts, None for the blank.
+pub(crate) struct Cell {
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    pub(crate) tile: Option<i64>,
+}
+
+/// Fills a board with tiles in row-major order, checking them the same way as
+/// `all_tiles_unique`.
+pub(crate) struct Placer<const W: usize, const H: usize> {
+    board: [[Option<u8>; H]; W],
+    seen: Vec<bool>,
+    has_blank: bool,
+    next: usize,
+}
+
+impl<const W: usize, const H: usize> Placer<W, H> {
+    pub(crate) fn new() -> Self {
+        Placer {
+            board: [[None; H]; W],
+            seen: vec![false; Board::<W, H>::MAX_TILE as usize + 1],
+            has_blank: false,
+            next: 0,
+        }
+    }
+
+    /// Puts the tile into the next cell, the callers make sure there are no more than `W * H`.
+    pub(crate) fn place(&mut self, cell: Cell) -> Result<(), ParseGameStateError> {
+        let Cell { line, column, tile } = cell;
+        let (x, y) = (self.next % W, self.next / W);
+        self.next += 1;
+        let Some(tile) = tile else {
+            if self.has_blank {
+                return Err(ParseGameStateError::SecondBlank { line, column });
+            }
+            self.has_blank = true;
+            return Ok(());
+        };
+        let max = Board::<W, H>::MAX_TILE;
+        if !(1..=max as i64).contains(&tile) {
+            return Err(ParseGameStateError::TileOutOfRange {
+                line,
+                column,
+                tile,
+                max,
+            });
+        }
+        let tile = tile as u8;
+        if std::mem::replace(&mut self.seen[tile as usize], true) {
+            return Err(ParseGameStateError::DuplicateTile { line, column, tile });
+        }
+        self.board[x][y] = Some(tile);
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> Board<W, H> {
+        Board::from_board(self.board)
+    }
+}
+
+/// Reads a tile number, 0 being the blank.
+fn number(text: &str, line: usize, column: usize) -> Result<Cell, ParseGameStateError> {
+    match text.parse::<i64>() {
+        Ok(tile) => Ok(Cell {
+            line,
+            column,
+            tile: (tile != 0).then_some(tile),
+        }),
+        Err(_) => Err(ParseGameStateError::NotANumber {
+            line,
+            column,
+            text: text.to_string(),
+        }),
+    }
+}
+
+/// Every character of `s` together with its line and column.
+fn positions(s: &str) -> impl Iterator<Item = (usize, usize, char)> + '_ {
+    (1..).zip(s.lines()).flat_map(|(line, text)| {
+        (1..)
+            .zip(text.chars())
+            .map(move |(column, c)| (line, column, c))
+    })
+}
+
+/// Splits `s` into the words between whitespace and commas.
+fn words(s: &str) -> Vec<(usize, usize, String)> {
+    let mut words: Vec<(usize, usize, String)> = vec![];
+    let mut in_word = false;
+    for (line, column, c) in positions(s) {
+        if c.is_whitespace() || c == ',' {
+            in_word = false;
+        } else if in_word && column > 1 {
+            words.last_mut().unwrap().2.push(c);
+        } else {
+            words.push((line, column, c.to_string()));
+            in_word = true;
+        }
+    }
+    words
+}
+
+/// Checks that there are exactly `W * H` cells. Too many are reported on the line of the first
+/// extra one, too few on the last line.
+fn check_count<const W: usize, const H: usize>(
+    lines: impl Iterator<Item = usize>,
+    s: &str,
+) -> Result<(), ParseGameStateError> {
+    let lines: Vec<usize> = lines.collect();
+    if lines.len() == W * H {
+        return Ok(());
+    }
+    let line = lines
+        .get(W * H)
+        .copied()
+        .unwrap_or_else(|| s.lines().count().max(1));
+    Err(ParseGameStateError::CellCount {
+        line,
+        expected: W * H,
+        found: lines.len(),
+    })
+}
+
+fn parse_flat<const W: usize, const H: usize>(s: &str) -> Result<Board<W, H>, ParseGameStateError> {
+    let words = words(s);
+    check_count::<W, H>(words.iter().map(|(line, _, _)| *line), s)?;
+    let mut placer = Placer::new();
+    for (line, column, text) in words {
+        placer.place(number(&text, line, column)?)?;
+    }
+    Ok(placer.finish())
+}
+
+fn parse_compact<const W: usize, const H: usize>(
+    s: &str,
+) -> Result<Board<W, H>, ParseGameStateError> {
+    let digits: Vec<(usize, usize, char)> = positions(s)
+        .filter(|(_, _, c)| !c.is_whitespace())
+        .collect();
+    check_count::<W, H>(digits.iter().map(|(line, _, _)| *line), s)?;
+    let mut placer = Placer::new();
+    for (line, column, c) in digits {
+        let Some(tile) = c.to_digit(36) else {
+            return Err(ParseGameStateError::NotANumber {
+                line,
+                column,
+                text: c.to_string(),
+            });
+        };
+        placer.place(Cell {
+            line,
+            column,
+            tile: (tile != 0).then_some(tile as i64),
+        })?;
+    }
+    Ok(placer.finish())
+}
+
+/// The part of JSON that boards are written in: arrays of numbers and `null`.
+enum Json {
+    Cell(Cell),
+    Array {
+        line: usize,
+        column: usize,
+        items: Vec<Json>,
+    },
+}
+
+impl Json {
+    fn position(&self) -> (usize, usize) {
+        match self {
+            Json::Cell(cell) => (cell.line, cell.column),
+            Json::Array { line, column, .. } => (*line, *column),
+        }
+    }
+}
+
+/// How deeply arrays may be nested, boards only need two levels but the reader recurses for every
+/// level and must not run out of stack.
+const MAX_JSON_DEPTH: usize = 64;
+
+struct JsonReader<I: Iterator<Item = (usize, usize, char)>> {
+    chars: std::iter::Peekable<I>,
+    /// where the input ends, for errors about missing characters
+    end: (usize, usize),
+}
+
+impl<I: Iterator<Item = (usize, usize, char)>> JsonReader<I> {
+    /// The next character that is not whitespace, without consuming it.
+    fn peek(&mut self) -> Option<(usize, usize, char)> {
+        while self.chars.next_if(|(_, _, c)| c.is_whitespace()).is_some() {}
+        self.chars.peek().copied()
+    }
+
+    fn error(&mut self, expected: &'static str) -> ParseGameStateError {
+        let (line, column) = self
+            .peek()
+            .map_or(self.end, |(line, column, _)| (line, column));
+        ParseGameStateError::Syntax {
+            line,
+            column,
+            expected,
+        }
+    }
+
+    /// Reads a number, `null` or an array nested in `depth` arrays.
+    fn value(&mut self, depth: usize) -> Result<Json, ParseGameStateError> {
+        let Some((line, column, c)) = self.peek() else {
+            return Err(self.error("a value"));
+        };
+        if c != '[' {
+            let mut text = String::new();
+            while let Some((_, _, c)) = self
+                .chars
+                .next_if(|(_, _, c)| c.is_alphanumeric() || *c == '-')
+            {
+                text.push(c);
+            }
+            if text.is_empty() {
+                return Err(self.error("a value"));
+            }
+            if text == "null" {
+                let tile = None;
+                return Ok(Json::Cell(Cell { line, column, tile }));
+            }
+            return Ok(Json::Cell(number(&text, line, column)?));
+        }
+        if depth == MAX_JSON_DEPTH {
+            return Err(self.error("fewer nested arrays"));
+        }
+        self.chars.next();
+        let mut items = vec![];
+        if self.peek().is_some_and(|(_, _, c)| c == ']') {
+            self.chars.next();
+            return Ok(Json::Array {
+                line,
+                column,
+                items,
+            });
+        }
+        loop {
+            items.push(self.value(depth + 1)?);
+            match self.peek() {
+                Some((_, _, ',')) => {
+                    self.chars.next();
+                }
+                Some((_, _, ']')) => {
+                    self.chars.next();
+                    return Ok(Json::Array {
+                        line,
+                        column,
+                        items,
+                    });
+                }
+                _ => return Err(self.error("',' or ']'")),
+            }
+        }
+    }
+}
+
+/// Places the numbers of an array, which must not contain arrays itself.
+fn place_cells<const W: usize, const H: usize>(
+    placer: &mut Placer<W, H>,
+    items: Vec<Json>,
+) -> Result<(), ParseGameStateError> {
+    for item in items {
+        match item {
+            Json::Cell(cell) => placer.place(cell)?,
+            Json::Array { line, column, .. } => {
+                return Err(ParseGameStateError::Syntax {
+                    line,
+                    column,
+                    expected: "a number",
+                })
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_json<const W: usize, const H: usize>(s: &str) -> Result<Board<W, H>, ParseGameStateError> {
+    let end = positions(s)
+        .last()
+        .map_or((1, 1), |(line, column, _)| (line, column + 1));
+    let mut reader = JsonReader {
+        chars: positions(s).peekable(),
+        end,
+    };
+    let value = reader.value(0)?;
+    if reader.peek().is_some() {
+        return Err(reader.error("the end of the input"));
+    }
+    let (line, column) = value.position();
+    let Json::Array { items, .. } = value else {
+        return Err(ParseGameStateError::Syntax {
+            line,
+            column,
+            expected: "'['",
+        });
+    };
+    let mut placer = Placer::new();
+    if !items.iter().any(|item| matches!(item, Json::Array { .. })) {
+        check_count::<W, H>(items.iter().map(|_| line), s)?;
+        place_cells(&mut placer, items)?;
+        return Ok(placer.finish());
+    }
+    // an array of rows
+    if items.len() != H {
+        return Err(ParseGameStateError::RowCount {
+            line,
+            expected: H,
+            found: items.len(),
+        });
+    }
+    for row in items {
+        let (line, column) = row.position();
+        let Json::Array { items, .. } = row else {
+            return Err(ParseGameStateError::Syntax {
+                line,
+                column,
+                expected: "an array",
+            });
+        };
+        if items.len() != W {
+            return Err(ParseGameStateError::CellCount {
+                line,
+                expected: W,
+                found: items.len(),
+            });
+        }
+        place_cells(&mut placer, items)?;
+    }
+    Ok(placer.finish())
+}
+
+impl<const W: usize, const H: usize> Board<W, H> {
+    /// Reads a board written in the given notation.
+    pub fn parse_notation(s: &str, notation: Notation) -> Result<Self, ParseGameStateError> {
+        match notation {
+            Notation::Grid => s.parse(),
+            Notation::Flat => parse_flat(s),
+            Notation::Compact => parse_compact(s),
+            Notation::Json => parse_json(s),
+        }
+    }
+
+    /// Reads a board in whatever notation `Notation::detect` finds.
+    pub fn parse_any(s: &str) -> Result<Self, ParseGameStateError> {
+        Self::parse_notation(s, Notation::detect(s))
+    }
+
+    /// Writes the board in the given notation. Returns None for the compact notation if a tile
+    /// does not fit into a single character.
+    pub fn to_notation(&self, notation: Notation) -> Option<String> {
+        let tiles = (0..H).flat_map(|y| (0..W).map(move |x| self.get(x as u8, y as u8)));
+        let numbers = tiles.map(|tile| tile.unwrap_or(0));
+        match notation {
+            Notation::Grid => Some(self.to_string()),
+            Notation::Flat => Some(
+                numbers
+                    .map(|tile| tile.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            Notation::Compact => numbers
+                .map(|tile| char::from_digit(tile as u32, 36).map(|c| c.to_ascii_uppercase()))
+                .collect(),
+            Notation::Json => {
+                let numbers: Vec<String> = numbers.map(|tile| tile.to_string()).collect();
+                let rows: Vec<String> = numbers
+                    .chunks(W)
+                    .map(|row| format!("[{}]", row.join(",")))
+                    .collect();
+                Some(format!("[{}]", rows.join(",")))
+            }
+        }
+    }
+}
+
+/// Why a move or a sequence of moves could not be parsed. Columns count from 1, in characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseMoveError {
+    /// A character that is not one of the move letters `R`, `L`, `D` and `U`.
+    UnknownMove { column: usize, found: char },
+    /// A repeat count that is zero or too large.
+    InvalidCount { column: usize, text: String },
+    /// A single move was expected, but the text holds `found` of them.
+    NotOneMove { found: usize },
+}
+
+impl std::fmt::Display for ParseMoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseMoveError::UnknownMove { column, found } => {
+                write!(f, "column {column}: '{found}' is not a move")
+            }
+            ParseMoveError::InvalidCount { column, text } => {
+                write!(f, "column {column}: '{text}' is not a valid repeat count")
+            }
+            ParseMoveError::NotOneMove { found } => {
+                write!(f, "expected a single move, found {found}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseMoveError {}
+
+impl Move {
+    /// The letter of the move, which names the direction the tile slides in: `R` for
+    /// `LeftToRight`, `L` for `RightToLeft`, `D` for `TopToBottom` and `U` for `BottomToTop`.
+    /// The blank goes the opposite way.
+    pub fn letter(self) -> char {
+        match self {
+            Move::LeftToRight => 'R',
+            Move::RightToLeft => 'L',
+            Move::TopToBottom => 'D',
+            Move::BottomToTop => 'U',
+        }
+    }
+
+    /// The move with the given letter, in either case.
+    pub fn from_letter(letter: char) -> Option<Move> {
+        match letter.to_ascii_uppercase() {
+            'R' => Some(Move::LeftToRight),
+            'L' => Some(Move::RightToLeft),
+            'D' => Some(Move::TopToBottom),
+            'U' => Some(Move::BottomToTop),
+            _ => None,
+        }
+    }
+
+    /// Writes the moves as one letter each, `"DRRU"`.
+    pub fn format_sequence(moves: &[Move]) -> String {
+        moves.iter().map(|m| m.letter()).collect()
+    }
+
+    /// Writes the moves with runs of the same move shortened, `"DR2U"` for `"DRRU"`.
+    pub fn format_run_length(moves: &[Move]) -> String {
+        let mut text = String::new();
+        for run in moves.chunk_by(|a, b| a == b) {
+            text.push(run[0].letter());
+            if run.len() > 1 {
+                text.push_str(&run.len().to_string());
+            }
+        }
+        text
+    }
+
+    /// Reads moves written by `format_sequence` or `format_run_length`. A letter can be followed
+    /// by a count to repeat it, whitespace and commas between the moves are ignored.
+    pub fn parse_sequence(s: &str) -> Result<Vec<Move>, ParseMoveError> {
+        let mut moves = vec![];
+        let mut chars = (1..).zip(s.chars()).peekable();
+        while let Some((column, c)) = chars.next() {
+            if c.is_whitespace() || c == ',' {
+                continue;
+            }
+            let Some(m) = Move::from_letter(c) else {
+                return Err(ParseMoveError::UnknownMove { column, found: c });
+            };
+            let mut count = String::new();
+            while let Some((_, digit)) = chars.next_if(|(_, c)| c.is_ascii_digit()) {
+                count.push(digit);
+            }
+            let repeat = match count.parse::<usize>() {
+                _ if count.is_empty() => 1,
+                Ok(repeat) if repeat > 0 => repeat,
+                _ => {
+                    return Err(ParseMoveError::InvalidCount {
+                        column: column + 1,
+                        text: count,
+                    })
+                }
+            };
+            moves.extend(std::iter::repeat_n(m, repeat));
+        }
+        Ok(moves)
+    }
+}
+
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.letter())
+    }
+}
+
+/// Parses a single move letter, see `Move::letter`.
+impl FromStr for Move {
+    type Err = ParseMoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match Move::parse_sequence(s)?.as_slice() {
+            &[m] => Ok(m),
+            moves => Err(ParseMoveError::NotOneMove { found: moves.len() }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GameState, Move};
+
+    const NOTATIONS: [Notation; 4] = [
+        Notation::Grid,
+        Notation::Flat,
+        Notation::Compact,
+        Notation::Json,
+    ];
+
+    #[test]
+    fn test_default_notations() {
+        let state = GameState::default();
+        let flat = "1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 0";
+        let json = "[[1,2,3,4],[5,6,7,8],[9,10,11,12],[13,14,15,0]]";
+        assert_eq!(state.to_notation(Notation::Flat).unwrap(), flat);
+        assert_eq!(
+            state.to_notation(Notation::Compact).unwrap(),
+            "123456789ABCDEF0"
+        );
+        assert_eq!(state.to_notation(Notation::Json).unwrap(), json);
+        assert_eq!(
+            state.to_notation(Notation::Grid).unwrap(),
+            state.to_string()
+        );
+
+        assert_eq!(Notation::detect(flat), Notation::Flat);
+        assert_eq!(Notation::detect("123456789abcdef0"), Notation::Compact);
+        assert_eq!(Notation::detect(json), Notation::Json);
+        assert_eq!(Notation::detect(&state.to_string()), Notation::Grid);
+        assert_eq!(Notation::detect("1,2,3"), Notation::Flat);
+
+        for text in [flat, "123456789abcdef0", json] {
+            assert_eq!(GameState::parse_any(text), Ok(state.clone()));
+        }
+        // other spellings of the same board
+        let spellings = [
+            "1, 2, 3, 4,\n5, 6, 7, 8,\n9, 10, 11, 12,\n13, 14, 15, 0",
+            "[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, null]",
+            " [ [1,2,3,4],\n [5,6,7,8],\n [9,10,11,12],\n [13,14,15,null] ] ",
+        ];
+        for text in spellings {
+            assert_eq!(GameState::parse_any(text), Ok(state.clone()));
+        }
+        let compact = GameState::parse_notation("1234\n5678\n9abc\ndef0", Notation::Compact);
+        assert_eq!(compact, Ok(state));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut state = GameState::default();
+        state.perform_moves(&[Move::TopToBottom, Move::LeftToRight, Move::TopToBottom]);
+        let mut eight = Board::<3, 3>::default();
+        eight.perform_moves(&[Move::LeftToRight, Move::TopToBottom]);
+        let mut big = Board::<6, 6>::default();
+        big.perform_moves(&[Move::TopToBottom, Move::LeftToRight]);
+        for notation in NOTATIONS {
+            let text = state.to_notation(notation).unwrap();
+            assert_eq!(
+                GameState::parse_notation(&text, notation),
+                Ok(state.clone())
+            );
+            assert_eq!(GameState::parse_any(&text), Ok(state.clone()));
+            let text = eight.to_notation(notation).unwrap();
+            assert_eq!(Board::parse_any(&text), Ok(eight.clone()));
+            let text = big.to_notation(notation).unwrap();
+            assert_eq!(Board::parse_any(&text), Ok(big.clone()));
+        }
+        assert_eq!(
+            Board::<6, 6>::default()
+                .to_notation(Notation::Compact)
+                .unwrap(),
+            "123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ0"
+        );
+        assert_eq!(
+            Board::<7, 6>::default().to_notation(Notation::Compact),
+            None
+        );
+    }
+
+    #[test]
+    fn test_validation() {
+        let error = GameState::parse_any("1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 15").unwrap_err();
+        assert_eq!(
+            error,
+            ParseGameStateError::DuplicateTile {
+                line: 1,
+                column: 37,
+                tile: 15
+            }
+        );
+        assert_eq!(
+            GameState::parse_any("1 2 3 4 5 6 7 8 9 10 11 12 13 14 0 0"),
+            Err(ParseGameStateError::SecondBlank {
+                line: 1,
+                column: 36
+            })
+        );
+        assert_eq!(
+            GameState::parse_any("1 2 3 4 5 6 7 8 9 10 11 12 13 14 16 0"),
+            Err(ParseGameStateError::TileOutOfRange {
+                line: 1,
+                column: 34,
+                tile: 16,
+                max: 15
+            })
+        );
+        assert_eq!(
+            GameState::parse_any("1 2 3\n4 5 6 7 8 9 10 11 12 13 14 15 x"),
+            Err(ParseGameStateError::NotANumber {
+                line: 2,
+                column: 31,
+                text: "x".to_string()
+            })
+        );
+        assert_eq!(
+            GameState::parse_any("1 2 3 4"),
+            Err(ParseGameStateError::CellCount {
+                line: 1,
+                expected: 16,
+                found: 4
+            })
+        );
+        assert_eq!(
+            GameState::parse_any("123456789ABCDEFG"),
+            Err(ParseGameStateError::TileOutOfRange {
+                line: 1,
+                column: 16,
+                tile: 16,
+                max: 15
+            })
+        );
+        assert_eq!(
+            GameState::parse_any("123456789ABCDE-0"),
+            Err(ParseGameStateError::NotANumber {
+                line: 1,
+                column: 15,
+                text: "-".to_string()
+            })
+        );
+        assert_eq!(
+            GameState::parse_any("123456789ABCDEF"),
+            Err(ParseGameStateError::CellCount {
+                line: 1,
+                expected: 16,
+                found: 15
+            })
+        );
+    }
+
+    #[test]
+    fn test_move_notation() {
+        use Move::*;
+        let moves = [TopToBottom, LeftToRight, LeftToRight, BottomToTop];
+        assert_eq!(Move::format_sequence(&moves), "DRRU");
+        assert_eq!(Move::format_run_length(&moves), "DR2U");
+        assert_eq!(Move::format_run_length(&[]), "");
+        assert_eq!(Move::parse_sequence("DRRU"), Ok(moves.to_vec()));
+        assert_eq!(Move::parse_sequence("dR2u"), Ok(moves.to_vec()));
+        assert_eq!(Move::parse_sequence(" D, R R\nU "), Ok(moves.to_vec()));
+        assert_eq!(Move::parse_sequence("L12").unwrap(), vec![RightToLeft; 12]);
+        assert_eq!(Move::parse_sequence(""), Ok(vec![]));
+
+        for m in [LeftToRight, RightToLeft, TopToBottom, BottomToTop] {
+            assert_eq!(m.to_string().parse(), Ok(m));
+        }
+        assert_eq!("u".parse(), Ok(BottomToTop));
+
+        // the letters name the direction the tile slides in
+        let mut state = GameState::default();
+        state.perform_moves(&Move::parse_sequence("RD").unwrap());
+        assert_eq!(state.blank(), Some((2, 2)));
+        assert_eq!(state.get(2, 3), Some(11));
+    }
+
+    #[test]
+    fn test_move_errors() {
+        assert_eq!(
+            Move::parse_sequence("RDX"),
+            Err(ParseMoveError::UnknownMove {
+                column: 3,
+                found: 'X'
+            })
+        );
+        assert_eq!(
+            Move::parse_sequence("2R"),
+            Err(ParseMoveError::UnknownMove {
+                column: 1,
+                found: '2'
+            })
+        );
+        assert_eq!(
+            Move::parse_sequence("R0"),
+            Err(ParseMoveError::InvalidCount {
+                column: 2,
+                text: "0".to_string()
+            })
+        );
+        let error = Move::parse_sequence("U99999999999999999999999").unwrap_err();
+        assert!(matches!(
+            error,
+            ParseMoveError::InvalidCount { column: 2, .. }
+        ));
+        assert_eq!(
+            "RR".parse::<Move>(),
+            Err(ParseMoveError::NotOneMove { found: 2 })
+        );
+        assert_eq!(
+            "R2".parse::<Move>(),
+            Err(ParseMoveError::NotOneMove { found: 2 })
+        );
+        assert_eq!(
+            Move::parse_sequence("DL?").unwrap_err().to_string(),
+            "column 3: '?' is not a move"
+        );
+    }
+
+    #[test]
+    fn test_json_errors() {
+        let syntax = |line, column, expected| {
+            Err::<GameState, _>(ParseGameStateError::Syntax {
+                line,
+                column,
+                expected,
+            })
+        };
+        assert_eq!(GameState::parse_any("[1, 2"), syntax(1, 6, "',' or ']'"));
+        assert_eq!(GameState::parse_any("[1 2]"), syntax(1, 4, "',' or ']'"));
+        assert_eq!(GameState::parse_any("[1, ]"), syntax(1, 5, "a value"));
+        assert_eq!(
+            GameState::parse_any("[[1,2,3,4],[5,6,7,8],[9,10,11,12],[13,14,15,0]] x"),
+            syntax(1, 49, "the end of the input")
+        );
+        assert_eq!(
+            GameState::parse_any("[[1,2,3,4],5,[9,10,11,12],[13,14,15,0]]"),
+            syntax(1, 12, "an array")
+        );
+        assert_eq!(
+            GameState::parse_any("[[1,2,3,4],\n[5,6,[7],8],[9,10,11,12],[13,14,15,0]]"),
+            syntax(2, 6, "a number")
+        );
+        let deep = "[".repeat(1_000_000);
+        assert_eq!(
+            GameState::parse_any(&deep),
+            syntax(1, MAX_JSON_DEPTH + 1, "fewer nested arrays")
+        );
+        assert_eq!(
+            GameState::parse_any("[[1,2,3,4],[5,6,7,8],[9,10,11,12]]"),
+            Err(ParseGameStateError::RowCount {
+                line: 1,
+                expected: 4,
+                found: 3
+            })
+        );
+        assert_eq!(
+            GameState::parse_any("[[1,2,3,4],\n[5,6,7,8,0],[9,10,11,12],[13,14,15]]"),
+            Err(ParseGameStateError::CellCount {
+                line: 2,
+                expected: 4,
+                found: 5
+            })
+        );
+        assert_eq!(
+            GameState::parse_any("[[1,2,3,4],[5,6,7,8],[9,10,true,12],[13,14,15,0]]"),
+            Err(ParseGameStateError::NotANumber {
+                line: 1,
+                column: 28,
+                text: "true".to_string()
+            })
+        );
+        let error = GameState::parse_notation("15", Notation::Json).unwrap_err();
+        assert_eq!(error.to_string(), "line 1, column 1: expected '['");
+    }
+}
@@ -0,0 +1,215 @@
+use crate::search::move_source;
+use crate::{Board, Move};
+
+/// Every nibble set to 1, used to look at all cells at once.
+const LOW_BITS: u64 = 0x1111_1111_1111_1111;
+
+/// A board of up to 16 cells packed into a single word: 4 bits per cell in row-major order,
+/// starting with the top left corner in the lowest nibble. The blank is stored as 0.
+/// Copying, hashing and comparing are single-word operations, which makes this the
+/// representation of choice for the solvers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PackedBoard<const W: usize, const H: usize>(u64);
+
+/// The packed 15-puzzle.
+pub type PackedState = PackedBoard<4, 4>;
+
+/// Returned when a board holds a tile that does not fit into 4 bits. Boards with more than 16
+/// cells report their largest tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidTile(pub u8);
+
+impl std::fmt::Display for InvalidTile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "tile {} cannot be packed into 4 bits", self.0)
+    }
+}
+
+impl std::error::Error for InvalidTile {}
+
+impl<const W: usize, const H: usize> Default for PackedBoard<W, H> {
+    fn default() -> Self {
+        // 1 to W * H - 1 in row-major order, blank in the bottom right corner
+        let mut bits = 0;
+        for index in 0..W * H - 1 {
+            bits |= (index as u64 + 1) << (index * 4);
+        }
+        PackedBoard(bits)
+    }
+}
+
+impl<const W: usize, const H: usize> PackedBoard<W, H> {
+    /// Whether boards of this size can be packed at all.
+    pub const FITS: bool = W * H <= 16;
+
+    /// Wraps the raw packed representation.
+    pub fn from_bits(bits: u64) -> Self {
+        PackedBoard(bits)
+    }
+
+    /// The raw packed representation.
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the tile at the row-major cell `index`, 0 for the blank.
+    pub(crate) fn tile(self, index: usize) -> u8 {
+        ((self.0 >> (index * 4)) & 0xF) as u8
+    }
+
+    /// Returns the tile at position x,y.
+    pub fn get(self, x: u8, y: u8) -> Option<u8> {
+        match self.tile(y as usize * W + x as usize) {
+            0 => None,
+            tile => Some(tile),
+        }
+    }
+
+    /// Row-major index of the first empty cell, `W * H` or more if there is none.
+    pub(crate) fn blank_index(self) -> usize {
+        // fold every nibble into its lowest bit, which is then 0 only for empty cells and the
+        // unused cells after the end of the board
+        let v = self.0;
+        let occupied = (v | v >> 1 | v >> 2 | v >> 3) & LOW_BITS;
+        ((!occupied & LOW_BITS).trailing_zeros() / 4) as usize
+    }
+
+    /// Returns the position of the blank.
+    pub fn blank(self) -> Option<(u8, u8)> {
+        let index = self.blank_index();
+        (index < W * H).then_some(((index % W) as u8, (index / W) as u8))
+    }
+
+    /// Moves the tile at `from` into the empty cell at `blank`.
+    pub(crate) fn slide(&mut self, from: usize, blank: usize) {
+        let tile = (self.0 >> (from * 4)) & 0xF;
+        self.0 = (self.0 & !(0xF << (from * 4))) | tile << (blank * 4);
+    }
+
+    /// Updates the state to reflect the move that was performed. Returns false if the move was
+    /// not possible.
+    pub fn perform_move(&mut self, m: Move) -> bool {
+        let blank = self.blank_index();
+        if blank >= W * H {
+            return false;
+        }
+        let Some(from) = move_source::<W, H>(blank, m) else {
+            return false;
+        };
+        self.slide(from, blank);
+        true
+    }
+
+    /// Returns the state after performing `m`, or None if the move is not possible.
+    pub fn apply(mut self, m: Move) -> Option<Self> {
+        self.perform_move(m).then_some(self)
+    }
+}
+
+impl<const W: usize, const H: usize> TryFrom<&Board<W, H>> for PackedBoard<W, H> {
+    type Error = InvalidTile;
+
+    fn try_from(state: &Board<W, H>) -> Result<Self, Self::Error> {
+        if !Self::FITS {
+            return Err(InvalidTile(Board::<W, H>::MAX_TILE));
+        }
+        let mut bits = 0;
+        for y in 0..H {
+            for x in 0..W {
+                let tile = state.get(x as u8, y as u8).unwrap_or(0);
+                if tile > 15 {
+                    return Err(InvalidTile(tile));
+                }
+                bits |= (tile as u64) << ((y * W + x) * 4);
+            }
+        }
+        Ok(PackedBoard(bits))
+    }
+}
+
+impl<const W: usize, const H: usize> From<PackedBoard<W, H>> for Board<W, H> {
+    fn from(packed: PackedBoard<W, H>) -> Self {
+        let mut state = Board::default();
+        for y in 0..H as u8 {
+            for x in 0..W as u8 {
+                state.set(x, y, packed.get(x, y));
+            }
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameState;
+
+    #[test]
+    fn test_size() {
+        assert_eq!(8, std::mem::size_of::<PackedState>());
+    }
+
+    #[test]
+    fn test_default() {
+        let packed = PackedState::default();
+        assert_eq!(PackedState::try_from(&GameState::default()), Ok(packed));
+        assert_eq!(GameState::from(packed), GameState::default());
+        assert_eq!(packed.get(0, 0), Some(1));
+        assert_eq!(packed.get(1, 0), Some(2));
+        assert_eq!(packed.get(0, 1), Some(5));
+        assert_eq!(packed.get(2, 3), Some(15));
+        assert_eq!(packed.get(3, 3), None);
+        assert_eq!(packed.blank(), Some((3, 3)));
+    }
+
+    #[test]
+    fn test_conversion() {
+        let mut state = GameState::default();
+        state.swap(0, 0, 3, 3);
+        state.swap(1, 2, 2, 1);
+        let packed = PackedState::try_from(&state).unwrap();
+        assert_eq!(packed.blank(), Some((0, 0)));
+        assert_eq!(GameState::from(packed), state);
+        assert_eq!(PackedState::from_bits(packed.bits()), packed);
+
+        state.set(2, 2, Some(16));
+        assert_eq!(PackedState::try_from(&state), Err(InvalidTile(16)));
+
+        // no blank at all
+        state.set(2, 2, Some(3));
+        state.set(0, 0, Some(3));
+        let packed = PackedState::try_from(&state).unwrap();
+        assert_eq!(packed.blank(), None);
+        assert_eq!(packed.apply(Move::LeftToRight), None);
+    }
+
+    #[test]
+    fn test_moves_match_game_state() {
+        let moves = [
+            Move::TopToBottom,
+            Move::LeftToRight,
+            Move::LeftToRight,
+            Move::TopToBottom,
+            Move::RightToLeft,
+            Move::BottomToTop,
+            Move::BottomToTop,
+            Move::LeftToRight,
+            Move::LeftToRight,
+            Move::TopToBottom,
+            Move::TopToBottom,
+            Move::TopToBottom,
+            Move::RightToLeft,
+            Move::LeftToRight,
+            Move::LeftToRight,
+            Move::LeftToRight,
+        ];
+        let mut state = GameState::default();
+        let mut packed = PackedState::default();
+        for m in moves {
+            assert_eq!(packed.apply(m).is_some(), state.clone().perform_move(m));
+            assert_eq!(packed.perform_move(m), state.perform_move(m));
+            assert_eq!(GameState::from(packed), state);
+            assert_eq!(PackedState::try_from(&state), Ok(packed));
+        }
+    }
+}
@@ -0,0 +1,568 @@
+use crate::rank::{partial_permutations, rank_partial, unrank_partial};
+use crate::GameState;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Identifies a pattern database file, followed by the format version.
+const MAGIC: &[u8; 8] = b"P15PDB\0\0";
+const VERSION: u16 = 2;
+
+/// Largest number of tiles in a pattern. A table has `16! / (16 - k)!` entries of a byte each, so
+/// eight tiles already take half a gigabyte.
+pub const MAX_PATTERN_SIZE: usize = 8;
+
+/// Korf and Felner's partition into three patterns of five tiles each.
+pub const PARTITION_5_5_5: [&[u8]; 3] =
+    [&[1, 2, 3, 5, 6], &[4, 7, 8, 11, 12], &[9, 10, 13, 14, 15]];
+/// Korf and Felner's partition into two patterns of six tiles and one of three.
+pub const PARTITION_6_6_3: [&[u8]; 3] =
+    [&[1, 5, 6, 9, 10, 13], &[7, 8, 11, 12, 14, 15], &[2, 3, 4]];
+
+/// Everything that can go wrong while building, saving or loading a pattern database.
+#[derive(Debug)]
+pub enum PdbError {
+    Io(std::io::Error),
+    /// A pattern contains a tile outside of 1..=15, shares a tile with another pattern or is
+    /// empty.
+    InvalidPartition,
+    /// A pattern has more than `MAX_PATTERN_SIZE` tiles, its table would not fit into memory.
+    PatternTooLarge(usize),
+    /// The file does not start with the pattern database magic bytes.
+    BadMagic,
+    UnsupportedVersion(u16),
+    /// The database was built for a board of a different size.
+    BoardSize {
+        width: u8,
+        height: u8,
+    },
+    /// The stored checksum does not match the header and tables, the file is corrupted.
+    ChecksumMismatch {
+        expected: u64,
+        actual: u64,
+    },
+}
+
+impl std::fmt::Display for PdbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PdbError::Io(e) => write!(f, "i/o error: {e}"),
+            PdbError::InvalidPartition => write!(f, "patterns must be disjoint sets of tiles 1-15"),
+            PdbError::PatternTooLarge(size) => write!(
+                f,
+                "patterns can have at most {MAX_PATTERN_SIZE} tiles, this one has {size}"
+            ),
+            PdbError::BadMagic => write!(f, "not a pattern database file"),
+            PdbError::UnsupportedVersion(v) => {
+                write!(f, "unsupported pattern database version {v}")
+            }
+            PdbError::BoardSize { width, height } => {
+                write!(f, "pattern database is for a {width}x{height} board")
+            }
+            PdbError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "checksum mismatch: expected {expected:#018x}, got {actual:#018x}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PdbError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PdbError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PdbError {
+    fn from(e: std::io::Error) -> Self {
+        PdbError::Io(e)
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Continues a 64-bit FNV-1a hash, used to detect corrupted files.
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// Number of ways to place `k` distinguishable tiles on the 16 cells.
+fn table_size(k: usize) -> usize {
+    partial_permutations(16, k).unwrap() as usize
+}
+
+/// Maps the row-major cell indices of the pattern tiles to a unique index in
+/// `0..table_size(positions.len())`.
+fn rank(positions: &[u8]) -> usize {
+    rank_partial(positions, 16) as usize
+}
+
+/// Inverse of `rank`.
+fn unrank(rank: usize, positions: &mut [u8]) {
+    unrank_partial(rank as u64, 16, positions)
+}
+
+/// Row-major indices of the cells next to `cell`.
+fn neighbors(cell: u8) -> impl Iterator<Item = u8> {
+    let (x, y) = (cell % 4, cell / 4);
+    [
+        (x > 0).then(|| cell - 1),
+        (x < 3).then(|| cell + 1),
+        (y > 0).then(|| cell - 4),
+        (y < 3).then(|| cell + 4),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// Minimal number of moves of the pattern tiles needed to bring them from any placement into the
+/// place they have in `GameState::default()`. Moves of the other tiles are free, which is what
+/// makes databases of disjoint patterns additive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PatternDatabase {
+    tiles: Vec<u8>,
+    /// indexed by the `rank` of the tile positions
+    distances: Vec<u8>,
+}
+
+impl PatternDatabase {
+    /// Retrograde breadth first search from the goal. All placements of the blank that can be
+    /// reached without moving a pattern tile are expanded together, so the levels of the search
+    /// correspond to pattern tile moves.
+    fn build(tiles: &[u8]) -> Self {
+        let k = tiles.len();
+        let size = table_size(k);
+        let mut distances = vec![u8::MAX; size];
+        // one bit for every combination of pattern placement and blank cell
+        let mut visited = vec![0u64; (size * 16).div_ceil(64)];
+        let is_visited = |visited: &[u64], rank: usize, blank: u8| {
+            let bit = rank * 16 + blank as usize;
+            visited[bit / 64] & (1 << (bit % 64)) != 0
+        };
+
+        let goal: Vec<u8> = tiles.iter().map(|t| t - 1).collect();
+        let mut frontier = vec![(rank(&goal) as u32, 15u8)];
+        let mut distance = 0u8;
+        let mut positions = vec![0; k];
+        let mut region = Vec::with_capacity(16);
+        while !frontier.is_empty() {
+            let mut next = vec![];
+            for (state_rank, blank) in frontier {
+                let state_rank = state_rank as usize;
+                if is_visited(&visited, state_rank, blank) {
+                    continue;
+                }
+                unrank(state_rank, &mut positions);
+                let occupied = positions.iter().fold(0u16, |acc, &p| acc | 1 << p);
+                if distances[state_rank] == u8::MAX {
+                    distances[state_rank] = distance;
+                }
+
+                // every cell the blank can reach without moving a pattern tile
+                region.clear();
+                region.push(blank);
+                let bit = state_rank * 16 + blank as usize;
+                visited[bit / 64] |= 1 << (bit % 64);
+                let mut i = 0;
+                while i < region.len() {
+                    let cell = region[i];
+                    i += 1;
+                    for n in neighbors(cell) {
+                        if occupied & (1 << n) != 0 {
+                            // move the pattern tile into the blank
+                            let tile = positions.iter().position(|&p| p == n).unwrap();
+                            positions[tile] = cell;
+                            let next_rank = rank(&positions);
+                            positions[tile] = n;
+                            if !is_visited(&visited, next_rank, n) {
+                                next.push((next_rank as u32, n));
+                            }
+                        } else if !is_visited(&visited, state_rank, n) {
+                            let bit = state_rank * 16 + n as usize;
+                            visited[bit / 64] |= 1 << (bit % 64);
+                            region.push(n);
+                        }
+                    }
+                }
+            }
+            frontier = next;
+            distance += 1;
+        }
+
+        PatternDatabase {
+            tiles: tiles.to_vec(),
+            distances,
+        }
+    }
+}
+
+/// Checks that the patterns are not empty, not too large and don't share tiles.
+fn check_partition<'a>(partition: impl IntoIterator<Item = &'a [u8]>) -> Result<(), PdbError> {
+    let mut seen = 0u16;
+    for pattern in partition {
+        if pattern.is_empty() {
+            return Err(PdbError::InvalidPartition);
+        }
+        for &tile in pattern {
+            if !(1..=15).contains(&tile) || seen & (1 << tile) != 0 {
+                return Err(PdbError::InvalidPartition);
+            }
+            seen |= 1 << tile;
+        }
+        if pattern.len() > MAX_PATTERN_SIZE {
+            return Err(PdbError::PatternTooLarge(pattern.len()));
+        }
+    }
+    Ok(())
+}
+
+/// Several pattern databases over disjoint sets of tiles. The sum of their entries never
+/// overestimates the number of moves needed to reach `GameState::default()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdditivePdb {
+    patterns: Vec<PatternDatabase>,
+}
+
+impl AdditivePdb {
+    /// Generates one database for every pattern of the partition. Patterns do not have to cover
+    /// all tiles, but they must not overlap.
+    pub fn build(partition: &[&[u8]]) -> Result<Self, PdbError> {
+        check_partition(partition.iter().copied())?;
+        let patterns = partition
+            .iter()
+            .map(|p| PatternDatabase::build(p))
+            .collect();
+        Ok(AdditivePdb { patterns })
+    }
+
+    /// The tiles of every pattern.
+    pub fn partition(&self) -> Vec<Vec<u8>> {
+        self.patterns.iter().map(|p| p.tiles.clone()).collect()
+    }
+
+    /// Lower bound on the number of moves needed to get from `state` to `GameState::default()`.
+    /// Returns 0 for states with invalid or duplicate tiles.
+    pub fn estimate(&self, state: &GameState) -> u32 {
+        if !state.all_tiles_unique() {
+            return 0;
+        }
+        let mut positions = [0; 16];
+        for y in 0..4 {
+            for x in 0..4 {
+                if let Some(tile) = state.get(x, y) {
+                    positions[tile as usize] = y * 4 + x;
+                }
+            }
+        }
+        self.estimate_positions(&positions, |tile| tile)
+    }
+
+    /// Sums up the entries for the tiles at the given row-major `positions`. `tile_for` maps the
+    /// tiles of the database to the tiles `positions` is indexed by.
+    pub(crate) fn estimate_positions(&self, positions: &[u8], tile_for: impl Fn(u8) -> u8) -> u32 {
+        let mut estimate = 0;
+        let mut pattern_positions = [0; 16];
+        for pattern in &self.patterns {
+            let k = pattern.tiles.len();
+            for (pos, &tile) in pattern_positions.iter_mut().zip(&pattern.tiles) {
+                *pos = positions[tile_for(tile) as usize];
+            }
+            estimate += pattern.distances[rank(&pattern_positions[..k])] as u32;
+        }
+        estimate
+    }
+
+    /// Writes the databases in the binary format read by `load`:
+    /// magic bytes, version (u16), board width and height (u8 each), the number of patterns (u8)
+    /// followed by the size and tiles of every pattern (u8 each), the FNV-1a checksum (u64) of
+    /// everything before it and the tables, and finally the tables themselves, one byte per
+    /// entry. All numbers are little endian.
+    pub fn save(&self, mut writer: impl Write) -> std::io::Result<()> {
+        writer.write_all(&self.header())?;
+        writer.write_all(&self.checksum().to_le_bytes())?;
+        for pattern in &self.patterns {
+            writer.write_all(&pattern.distances)?;
+        }
+        writer.flush()
+    }
+
+    /// Reads databases written by `save`, checking the header and the checksum.
+    pub fn load(mut reader: impl Read) -> Result<Self, PdbError> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(PdbError::BadMagic);
+        }
+        let mut version = [0; 2];
+        reader.read_exact(&mut version)?;
+        let version = u16::from_le_bytes(version);
+        if version != VERSION {
+            return Err(PdbError::UnsupportedVersion(version));
+        }
+        let mut header = [0; 3];
+        reader.read_exact(&mut header)?;
+        let [width, height, count] = header;
+        if (width, height) != (4, 4) {
+            return Err(PdbError::BoardSize { width, height });
+        }
+
+        let mut partition = vec![];
+        for _ in 0..count {
+            let mut k = [0];
+            reader.read_exact(&mut k)?;
+            let mut tiles = vec![0; k[0] as usize];
+            reader.read_exact(&mut tiles)?;
+            partition.push(tiles);
+        }
+        check_partition(partition.iter().map(Vec::as_slice))?;
+
+        let mut checksum = [0; 8];
+        reader.read_exact(&mut checksum)?;
+        let expected = u64::from_le_bytes(checksum);
+        let mut patterns = vec![];
+        for tiles in partition {
+            // a corrupted header can claim tables of terabytes, so only allocate what is there
+            let size = table_size(tiles.len());
+            let mut distances = vec![];
+            (&mut reader)
+                .take(size as u64)
+                .read_to_end(&mut distances)?;
+            if distances.len() != size {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+            patterns.push(PatternDatabase { tiles, distances });
+        }
+        let pdb = AdditivePdb { patterns };
+        let actual = pdb.checksum();
+        if actual != expected {
+            return Err(PdbError::ChecksumMismatch { expected, actual });
+        }
+        Ok(pdb)
+    }
+
+    /// Saves the databases to a file, see `save` for the format.
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        self.save(BufWriter::new(File::create(path)?))
+    }
+
+    /// Loads databases from a file written by `save_to_file`.
+    pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self, PdbError> {
+        Self::load(BufReader::new(File::open(path)?))
+    }
+
+    /// Everything `save` writes before the checksum.
+    fn header(&self) -> Vec<u8> {
+        let mut header = MAGIC.to_vec();
+        header.extend(VERSION.to_le_bytes());
+        header.extend([4, 4, self.patterns.len() as u8]);
+        for pattern in &self.patterns {
+            header.push(pattern.tiles.len() as u8);
+            header.extend(&pattern.tiles);
+        }
+        header
+    }
+
+    fn checksum(&self) -> u64 {
+        self.patterns
+            .iter()
+            .fold(fnv1a(FNV_OFFSET, &self.header()), |hash, p| {
+                fnv1a(hash, &p.distances)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_shortest_path_idastar, Move};
+
+    #[test]
+    fn test_rank_round_trip() {
+        assert_eq!(table_size(1), 16);
+        assert_eq!(table_size(3), 16 * 15 * 14);
+        let mut positions = [0; 3];
+        for r in 0..table_size(3) {
+            unrank(r, &mut positions);
+            assert_ne!(positions[0], positions[1]);
+            assert_ne!(positions[0], positions[2]);
+            assert_ne!(positions[1], positions[2]);
+            assert_eq!(rank(&positions), r);
+        }
+    }
+
+    #[test]
+    fn test_single_tiles_are_manhattan() {
+        // with one tile per pattern, moving the blank around is free, so every entry is exactly
+        // the Manhattan distance of that tile
+        let singles: Vec<[u8; 1]> = (1..=15).map(|t| [t]).collect();
+        let partition: Vec<&[u8]> = singles.iter().map(|t| t.as_slice()).collect();
+        let pdb = AdditivePdb::build(&partition).unwrap();
+        assert_eq!(pdb.estimate(&GameState::default()), 0);
+
+        let mut state = GameState::default();
+        state.swap(0, 0, 3, 2);
+        assert_eq!(pdb.estimate(&state), 10);
+        state.perform_moves(&[Move::TopToBottom, Move::LeftToRight]);
+        state.swap(1, 1, 2, 3);
+        let manhattan: u32 = (0..4u8)
+            .flat_map(|x| (0..4u8).map(move |y| (x, y)))
+            .filter_map(|(x, y)| {
+                let tile = state.get(x, y)?;
+                let (gx, gy) = ((tile - 1) % 4, (tile - 1) / 4);
+                Some((x.abs_diff(gx) + y.abs_diff(gy)) as u32)
+            })
+            .sum();
+        assert_eq!(pdb.estimate(&state), manhattan);
+    }
+
+    #[test]
+    fn test_pattern_distances() {
+        let pdb = AdditivePdb::build(&[&[1, 2], &[5]]).unwrap();
+        assert_eq!(pdb.partition(), vec![vec![1, 2], vec![5]]);
+        assert_eq!(pdb.estimate(&GameState::default()), 0);
+
+        // swapping 1 and 2 takes 4 moves of pattern tiles: one has to step out of the row to let
+        // the other one pass
+        let mut state = GameState::default();
+        state.swap(0, 0, 1, 0);
+        assert_eq!(pdb.estimate(&state), 4);
+
+        // never more than the real distance
+        let mut state = GameState::default();
+        let moves = [
+            Move::TopToBottom,
+            Move::TopToBottom,
+            Move::LeftToRight,
+            Move::LeftToRight,
+            Move::TopToBottom,
+            Move::LeftToRight,
+            Move::BottomToTop,
+            Move::RightToLeft,
+        ];
+        assert_eq!(state.perform_moves(&moves), moves.len());
+        let distance = find_shortest_path_idastar(state.clone(), GameState::default())
+            .into_path()
+            .unwrap()
+            .len();
+        assert!(pdb.estimate(&state) as usize <= distance);
+        assert!(pdb.estimate(&state) > 0);
+    }
+
+    #[test]
+    fn test_invalid_partition() {
+        assert!(matches!(
+            AdditivePdb::build(&[&[1, 2], &[2, 3]]),
+            Err(PdbError::InvalidPartition)
+        ));
+        assert!(matches!(
+            AdditivePdb::build(&[&[0, 1]]),
+            Err(PdbError::InvalidPartition)
+        ));
+        assert!(matches!(
+            AdditivePdb::build(&[&[16]]),
+            Err(PdbError::InvalidPartition)
+        ));
+        assert!(matches!(
+            AdditivePdb::build(&[&[]]),
+            Err(PdbError::InvalidPartition)
+        ));
+        // rejected before trying to allocate 16!/6! bytes
+        assert!(matches!(
+            AdditivePdb::build(&[&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]]),
+            Err(PdbError::PatternTooLarge(10))
+        ));
+    }
+
+    #[test]
+    fn test_save_load() {
+        let pdb = AdditivePdb::build(&[&[1, 2, 3], &[4, 8]]).unwrap();
+        let mut bytes = vec![];
+        pdb.save(&mut bytes).unwrap();
+        assert_eq!(&bytes[..8], MAGIC);
+        assert_eq!(bytes.len(), 8 + 2 + 3 + 4 + 3 + 8 + 16 * 15 * 14 + 16 * 15);
+        assert_eq!(AdditivePdb::load(bytes.as_slice()).unwrap(), pdb);
+
+        // flipping a single entry is detected
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            AdditivePdb::load(corrupted.as_slice()),
+            Err(PdbError::ChecksumMismatch { .. })
+        ));
+
+        // so is a different partition of the same shape
+        let mut swapped = bytes.clone();
+        swapped.swap(14, 15);
+        assert!(matches!(
+            AdditivePdb::load(swapped.as_slice()),
+            Err(PdbError::ChecksumMismatch { .. })
+        ));
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert!(matches!(
+            AdditivePdb::load(wrong_magic.as_slice()),
+            Err(PdbError::BadMagic)
+        ));
+
+        let mut wrong_size = bytes.clone();
+        wrong_size[10] = 3;
+        assert!(matches!(
+            AdditivePdb::load(wrong_size.as_slice()),
+            Err(PdbError::BoardSize {
+                width: 3,
+                height: 4
+            })
+        ));
+
+        assert!(matches!(
+            AdditivePdb::load(&bytes[..100]),
+            Err(PdbError::Io(_))
+        ));
+
+        // a header claiming a table of all 15 tiles is rejected, one of the largest possible size
+        // is not believed before the table is read
+        let mut huge = bytes[..12].to_vec();
+        huge.extend([1, 15]);
+        huge.extend(1..=15);
+        huge.extend([0; 100]);
+        assert!(matches!(
+            AdditivePdb::load(huge.as_slice()),
+            Err(PdbError::PatternTooLarge(15))
+        ));
+        let mut large = bytes[..12].to_vec();
+        large.extend([1, 8]);
+        large.extend(1..=8);
+        large.extend([0; 100]);
+        assert!(matches!(
+            AdditivePdb::load(large.as_slice()),
+            Err(PdbError::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof
+        ));
+
+        let path = std::env::temp_dir().join(format!("puzzle15-pdb-{}.bin", std::process::id()));
+        pdb.save_to_file(&path).unwrap();
+        let loaded = AdditivePdb::load_from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), pdb);
+    }
+
+    /// Generates the full 5-5-5 databases, run with `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore]
+    fn test_build_5_5_5() {
+        let pdb = AdditivePdb::build(&PARTITION_5_5_5).unwrap();
+        assert_eq!(pdb.estimate(&GameState::default()), 0);
+        for pattern in &pdb.patterns {
+            assert_eq!(pattern.distances.len(), table_size(5));
+            // every placement of the tiles can be reached
+            assert!(pattern.distances.iter().all(|&d| d != u8::MAX));
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use crate::{find_shortest_path_idastar_budget, Board, Move, SolveOutcome};
+use std::io::{BufReader, Read, Write};
+
+/// What the player can ask for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Move(Move),
+    /// Show the next move of a shortest solution.
+    Hint,
+    Undo,
+    /// Go back to the starting position.
+    Restart,
+    Quit,
+}
+
+/// Turns the bytes typed on a terminal into keys. The arrow keys and WASD slide the tile next to
+/// the blank in that direction, `h` asks for a hint, `u` undoes, `r` restarts and `q` or Ctrl-C
+/// quits. Everything else is ignored.
+pub struct Keys<R> {
+    bytes: std::io::Bytes<BufReader<R>>,
+    /// a byte read after ESC that did not start an arrow key
+    pending: Option<u8>,
+}
+
+impl<R: Read> Keys<R> {
+    pub fn new(reader: R) -> Self {
+        Keys {
+            bytes: BufReader::new(reader).bytes(),
+            pending: None,
+        }
+    }
+
+    fn next_byte(&mut self) -> Option<u8> {
+        if let Some(byte) = self.pending.take() {
+            return Some(byte);
+        }
+        self.bytes.next()?.ok()
+    }
+}
+
+impl<R: Read> Iterator for Keys<R> {
+    type Item = Key;
+
+    fn next(&mut self) -> Option<Key> {
+        loop {
+            let key = match self.next_byte()? {
+                b'w' | b'W' => Key::Move(Move::BottomToTop),
+                b'a' | b'A' => Key::Move(Move::RightToLeft),
+                b's' | b'S' => Key::Move(Move::TopToBottom),
+                b'd' | b'D' => Key::Move(Move::LeftToRight),
+                b'h' | b'H' => Key::Hint,
+                b'u' | b'U' => Key::Undo,
+                b'r' | b'R' => Key::Restart,
+                // Ctrl-C arrives as a byte when the terminal does not turn it into a signal
+                b'q' | b'Q' | 0x03 => Key::Quit,
+                // arrow keys are sent as ESC [ A to ESC [ D, other keys like Home or Ctrl+Up send
+                // longer sequences that end in a byte from 0x40 to 0x7e and are dropped whole
+                0x1b => match self.next_byte() {
+                    Some(b'[') => match self.next_byte()? {
+                        b'A' => Key::Move(Move::BottomToTop),
+                        b'B' => Key::Move(Move::TopToBottom),
+                        b'C' => Key::Move(Move::LeftToRight),
+                        b'D' => Key::Move(Move::RightToLeft),
+                        mut byte => {
+                            while !(0x40..=0x7e).contains(&byte) {
+                                byte = self.next_byte()?;
+                            }
+                            continue;
+                        }
+                    },
+                    // a lone ESC, the byte after it is a key of its own
+                    byte => {
+                        self.pending = byte;
+                        continue;
+                    }
+                },
+                _ => continue,
+            };
+            return Some(key);
+        }
+    }
+}
+
+/// What happened after a key press.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Moved(Move),
+    /// The move is not possible in this position.
+    Blocked(Move),
+    Undone(Move),
+    NothingToUndo,
+    Restarted,
+    Hint(Hint),
+    Quit,
+}
+
+/// The answer to asking for a hint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hint {
+    /// The next move of a shortest solution.
+    Next(Move),
+    /// The game is already solved.
+    Solved,
+    /// The game can't be solved.
+    Unsolvable,
+    /// The search for a solution gave up after `HINT_MAX_NODES` states.
+    TooFar,
+}
+
+/// Number of states the search for a hint expands before giving up, so a hint for a position far
+/// from solved doesn't freeze the game.
+pub const HINT_MAX_NODES: u64 = 1_000_000;
+
+/// A game in progress, starting from some position and trying to reach the default state.
+#[derive(Debug, Clone)]
+pub struct Game<const W: usize = 4, const H: usize = 4> {
+    start: Board<W, H>,
+    state: Board<W, H>,
+    /// the moves made since the start, without the ones that were undone
+    history: Vec<Move>,
+}
+
+impl<const W: usize, const H: usize> Game<W, H> {
+    pub fn new(start: Board<W, H>) -> Self {
+        Game {
+            state: start.clone(),
+            start,
+            history: vec![],
+        }
+    }
+
+    pub fn state(&self) -> &Board<W, H> {
+        &self.state
+    }
+
+    /// Number of moves made, undone moves don't count.
+    pub fn moves(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.state == Board::default()
+    }
+
+    /// The next move of a shortest solution, if one is found within `HINT_MAX_NODES` states.
+    pub fn hint(&self) -> Hint {
+        let report =
+            find_shortest_path_idastar_budget(self.state.clone(), Board::default(), HINT_MAX_NODES);
+        match report.outcome {
+            SolveOutcome::Solved(path) => path.first().map_or(Hint::Solved, |&m| Hint::Next(m)),
+            SolveOutcome::Unsolvable => Hint::Unsolvable,
+            SolveOutcome::BudgetExhausted { .. } | SolveOutcome::NoPathWithin(_) => Hint::TooFar,
+        }
+    }
+
+    pub fn press(&mut self, key: Key) -> Event {
+        match key {
+            Key::Move(m) => {
+                if self.state.perform_move(m) {
+                    self.history.push(m);
+                    Event::Moved(m)
+                } else {
+                    Event::Blocked(m)
+                }
+            }
+            Key::Undo => match self.history.pop() {
+                Some(m) => {
+                    self.state.undo_move(m);
+                    Event::Undone(m)
+                }
+                None => Event::NothingToUndo,
+            },
+            Key::Restart => {
+                self.state = self.start.clone();
+                self.history.clear();
+                Event::Restarted
+            }
+            Key::Hint => Event::Hint(self.hint()),
+            Key::Quit => Event::Quit,
+        }
+    }
+}
+
+/// How a game loop ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ending {
+    Solved {
+        moves: usize,
+    },
+    Quit,
+    /// The keys ran out before the game was over.
+    InputClosed,
+}
+
+/// Plays `game` with the given keys, writing the board after every key press to `out`.
+pub fn play<const W: usize, const H: usize>(
+    game: &mut Game<W, H>,
+    keys: impl IntoIterator<Item = Key>,
+    out: &mut impl Write,
+) -> std::io::Result<Ending> {
+    writeln!(
+        out,
+        "arrows or WASD: move, h: hint, u: undo, r: restart, q: quit"
+    )?;
+    write!(out, "{}", game.state())?;
+    writeln!(out, "moves: {}", game.moves())?;
+    if game.is_solved() {
+        writeln!(out, "already solved")?;
+        return Ok(Ending::Solved {
+            moves: game.moves(),
+        });
+    }
+    for key in keys {
+        let message = match game.press(key) {
+            Event::Quit => return Ok(Ending::Quit),
+            Event::Moved(_) | Event::Undone(_) => None,
+            Event::Blocked(m) => Some(format!("{m} is not possible here")),
+            Event::NothingToUndo => Some("nothing to undo".to_string()),
+            Event::Restarted => Some("back to the start".to_string()),
+            Event::Hint(Hint::Next(m)) => Some(format!("hint: {m}")),
+            Event::Hint(Hint::Solved) => Some("no hint, the board is already solved".to_string()),
+            Event::Hint(Hint::Unsolvable) => {
+                Some("no hint, this position can't be solved".to_string())
+            }
+            Event::Hint(Hint::TooFar) => Some("no hint (too far)".to_string()),
+        };
+        writeln!(out)?;
+        write!(out, "{}", game.state())?;
+        writeln!(out, "moves: {}", game.moves())?;
+        if let Some(message) = message {
+            writeln!(out, "{message}")?;
+        }
+        if game.is_solved() {
+            writeln!(out, "solved in {} moves", game.moves())?;
+            return Ok(Ending::Solved {
+                moves: game.moves(),
+            });
+        }
+    }
+    Ok(Ending::InputClosed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameState;
+
+    fn scrambled(moves: &str) -> GameState {
+        let mut state = GameState::default();
+        state.perform_moves(&Move::parse_sequence(moves).unwrap());
+        state
+    }
+
+    #[test]
+    fn test_keys() {
+        let keys: Vec<Key> =
+            Keys::new("wasd\x1b[A\x1b[B\x1b[C\x1b[Dhurq x\x1b[Z\x03".as_bytes()).collect();
+        use Move::*;
+        assert_eq!(
+            keys,
+            vec![
+                Key::Move(BottomToTop),
+                Key::Move(RightToLeft),
+                Key::Move(TopToBottom),
+                Key::Move(LeftToRight),
+                Key::Move(BottomToTop),
+                Key::Move(TopToBottom),
+                Key::Move(LeftToRight),
+                Key::Move(RightToLeft),
+                Key::Hint,
+                Key::Undo,
+                Key::Restart,
+                Key::Quit,
+                Key::Quit,
+            ]
+        );
+
+        // the byte after a lone ESC is not swallowed
+        let keys: Vec<Key> = Keys::new(&b"\x1bq"[..]).collect();
+        assert_eq!(keys, vec![Key::Quit]);
+        // modified arrows, Home and the like don't leak their last byte as a move
+        let keys: Vec<Key> = Keys::new(&b"\x1b[1;5A"[..]).collect();
+        assert_eq!(keys, vec![]);
+        let keys: Vec<Key> = Keys::new(&b"\x1b[1;5Dq\x1b[Hw\x1b[5~"[..]).collect();
+        assert_eq!(keys, vec![Key::Quit, Key::Move(Move::BottomToTop)]);
+        let keys: Vec<Key> = Keys::new(&b"\x1b\x1b[Aw\x1b"[..]).collect();
+        assert_eq!(
+            keys,
+            vec![Key::Move(Move::BottomToTop), Key::Move(Move::BottomToTop)]
+        );
+    }
+
+    #[test]
+    fn test_game() {
+        let mut game = Game::new(scrambled("RD"));
+        assert!(!game.is_solved());
+        assert_eq!(game.hint(), Hint::Next(Move::BottomToTop));
+        assert_eq!(
+            game.press(Key::Move(Move::TopToBottom)),
+            Event::Moved(Move::TopToBottom)
+        );
+        assert_eq!(game.moves(), 1);
+        assert_eq!(game.press(Key::Undo), Event::Undone(Move::TopToBottom));
+        assert_eq!(game.press(Key::Undo), Event::NothingToUndo);
+        assert_eq!(game.state(), &scrambled("RD"));
+
+        game.press(Key::Move(Move::BottomToTop));
+        assert_eq!(
+            game.press(Key::Hint),
+            Event::Hint(Hint::Next(Move::RightToLeft))
+        );
+        game.press(Key::Move(Move::RightToLeft));
+        assert!(game.is_solved());
+        assert_eq!(game.press(Key::Hint), Event::Hint(Hint::Solved));
+        let mut swapped = GameState::default();
+        swapped.swap(0, 0, 1, 0);
+        assert_eq!(Game::new(swapped).hint(), Hint::Unsolvable);
+        assert_eq!(
+            game.press(Key::Move(Move::BottomToTop)),
+            Event::Blocked(Move::BottomToTop)
+        );
+        assert_eq!(game.press(Key::Restart), Event::Restarted);
+        assert_eq!((game.state(), game.moves()), (&scrambled("RD"), 0));
+    }
+
+    #[test]
+    fn test_play() {
+        // the second w is blocked, the blank is in the bottom row by then
+        let mut game = Game::new(scrambled("RD"));
+        let mut out = vec![];
+        let keys = Keys::new("wwha".as_bytes());
+        assert_eq!(
+            play(&mut game, keys, &mut out).unwrap(),
+            Ending::Solved { moves: 2 }
+        );
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("U is not possible here"));
+        assert!(out.contains("hint: L"));
+        assert!(out.ends_with(&format!(
+            "{}moves: 2\nsolved in 2 moves\n",
+            GameState::default()
+        )));
+
+        let mut game = Game::new(scrambled("RD"));
+        let mut out = vec![];
+        assert_eq!(
+            play(&mut game, [Key::Undo, Key::Quit], &mut out).unwrap(),
+            Ending::Quit
+        );
+        assert!(String::from_utf8(out).unwrap().contains("nothing to undo"));
+        let ending = play(&mut game, [Key::Move(Move::TopToBottom)], &mut vec![]).unwrap();
+        assert_eq!(ending, Ending::InputClosed);
+
+        // a solved start ends the game before any key is read
+        let mut game = Game::new(GameState::default());
+        let mut out = vec![];
+        assert_eq!(
+            play(&mut game, [Key::Hint], &mut out).unwrap(),
+            Ending::Solved { moves: 0 }
+        );
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.ends_with("moves: 0\nalready solved\n"));
+    }
+
+    #[test]
+    fn test_hint_too_far() {
+        let start = Board::<5, 5>::random_walk(200, 1).0;
+        let mut game = Game::new(start);
+        let mut out = vec![];
+        assert_eq!(
+            play(&mut game, [Key::Hint], &mut out).unwrap(),
+            Ending::InputClosed
+        );
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .ends_with("no hint (too far)\n"));
+    }
+}
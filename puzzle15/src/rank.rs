@@ -0,0 +1,272 @@
+use crate::Board;
+
+/// Index of the `n`th (counting from 0) set bit of `word`. Binary search over the popcounts of
+/// the halves, so it takes the same six steps for every word.
+fn select(mut word: u64, mut n: u32) -> u32 {
+    let mut index = 0;
+    let mut width = 32;
+    while width > 0 {
+        let low = word & ((1 << width) - 1);
+        let count = low.count_ones();
+        if n >= count {
+            n -= count;
+            word >>= width;
+            index += width;
+        } else {
+            word = low;
+        }
+        width /= 2;
+    }
+    index
+}
+
+/// Number of ways to place `k` distinguishable tiles on `cells` cells, `cells! / (cells - k)!`,
+/// or None if that does not fit into a `u64`.
+pub(crate) fn partial_permutations(cells: usize, k: usize) -> Option<u64> {
+    (cells - k + 1..=cells).try_fold(1u64, |count, n| count.checked_mul(n as u64))
+}
+
+/// Maps distinct cell indices (below `cells`, at most 64) to their Lehmer code: a unique index
+/// in `0..partial_permutations(cells, positions.len())` that preserves the lexicographic order.
+pub(crate) fn rank_partial(positions: &[u8], cells: usize) -> u64 {
+    let mut used = 0u64;
+    let mut rank = 0;
+    for (i, &pos) in positions.iter().enumerate() {
+        // number of free cells before this one
+        let digit = pos as u64 - (used & ((1 << pos) - 1)).count_ones() as u64;
+        rank = rank * (cells - i) as u64 + digit;
+        used |= 1 << pos;
+    }
+    rank
+}
+
+/// Inverse of `rank_partial`, fills in `positions`.
+pub(crate) fn unrank_partial(mut rank: u64, cells: usize, positions: &mut [u8]) {
+    // split the rank into its mixed radix digits, the last position changes fastest
+    for i in (0..positions.len()).rev() {
+        let radix = (cells - i) as u64;
+        positions[i] = (rank % radix) as u8;
+        rank /= radix;
+    }
+    let mut free = u64::MAX >> (64 - cells);
+    for pos in positions {
+        let cell = select(free, *pos as u32);
+        *pos = cell as u8;
+        free &= !(1 << cell);
+    }
+}
+
+impl<const W: usize, const H: usize> Board<W, H> {
+    /// Fails to compile for boards with more states than a `u64` can number, 21! > 2^64.
+    const RANKABLE: () = assert!(W * H <= 20, "only boards of up to 20 cells can be ranked");
+
+    /// Number of arrangements of the tiles and the blank, `(W * H)!`.
+    pub const PERMUTATIONS: u64 = {
+        let () = Self::RANKABLE;
+        let mut count = 1;
+        let mut n = 2;
+        while n <= W * H {
+            count *= n as u64;
+            n += 1;
+        }
+        count
+    };
+
+    /// Numbers every arrangement of the tiles with a unique index in `0..PERMUTATIONS`, the
+    /// default state being 0. This is the Lehmer code of the cells holding tile 1, 2, and so on.
+    /// Returns None if the state has invalid or duplicate tiles.
+    ///
+    /// Only compiles for boards of up to 20 cells, 4x5 at most, because 21! is more than a `u64`
+    /// can hold. Boards like 5x5 can still rank patterns of their tiles with `pattern_rank`.
+    pub fn rank(&self) -> Option<u64> {
+        let () = Self::RANKABLE;
+        let tiles: Vec<u8> = (1..=Self::MAX_TILE).collect();
+        self.pattern_rank(&tiles)
+    }
+
+    /// Inverse of `rank`, with the same limit on the board size. Returns None if `rank` is not
+    /// below `PERMUTATIONS`.
+    pub fn unrank(rank: u64) -> Option<Self> {
+        if rank >= Self::PERMUTATIONS {
+            return None;
+        }
+        let mut board = [[None; H]; W];
+        for (tile, (x, y)) in (1..).zip(Self::pattern_unrank(W * H - 1, rank)?) {
+            board[x as usize][y as usize] = Some(tile);
+        }
+        Some(Board::from_board(board))
+    }
+
+    /// Number of ways to place `k` tiles on the board, `(W * H)! / (W * H - k)!`. Returns None
+    /// if that does not fit into a `u64`.
+    pub fn pattern_count(k: usize) -> Option<u64> {
+        (k <= W * H).then(|| partial_permutations(W * H, k))?
+    }
+
+    /// Numbers the positions of just the `tiles` with a unique index in
+    /// `0..pattern_count(tiles.len())`, which is what a pattern database needs.
+    /// Returns None if a tile is listed twice or not on the board, the board holds a tile twice or
+    /// it has more than 64 cells.
+    pub fn pattern_rank(&self, tiles: &[u8]) -> Option<u64> {
+        if W * H > 64 {
+            return None;
+        }
+        Self::pattern_count(tiles.len())?;
+        let mut cell_of = vec![None; W * H];
+        for y in 0..H {
+            for x in 0..W {
+                if let Some(tile) = self.board[x][y] {
+                    let cell = cell_of.get_mut(tile as usize)?;
+                    if cell.is_some() {
+                        return None;
+                    }
+                    *cell = Some((y * W + x) as u8);
+                }
+            }
+        }
+        let mut positions = Vec::with_capacity(tiles.len());
+        let mut used = 0u64;
+        for &tile in tiles {
+            let cell = (*cell_of.get(tile as usize)?)?;
+            if used & 1 << cell != 0 {
+                return None;
+            }
+            used |= 1 << cell;
+            positions.push(cell);
+        }
+        Some(rank_partial(&positions, W * H))
+    }
+
+    /// Inverse of `pattern_rank` for a pattern of `k` tiles, returns their positions in the
+    /// order the tiles were listed.
+    /// Returns None if `rank` is out of range or the board has more than 64 cells.
+    pub fn pattern_unrank(k: usize, rank: u64) -> Option<Vec<(u8, u8)>> {
+        if W * H > 64 || rank >= Self::pattern_count(k)? {
+            return None;
+        }
+        let mut positions = vec![0; k];
+        unrank_partial(rank, W * H, &mut positions);
+        let position = |cell: u8| ((cell as usize % W) as u8, (cell as usize / W) as u8);
+        Some(positions.into_iter().map(position).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scramble::Rng;
+    use crate::{GameState, Move};
+
+    #[test]
+    fn test_select() {
+        assert_eq!(select(1, 0), 0);
+        assert_eq!(select(0b1011_0000, 0), 4);
+        assert_eq!(select(0b1011_0000, 1), 5);
+        assert_eq!(select(0b1011_0000, 2), 7);
+        assert_eq!(select(u64::MAX, 63), 63);
+        assert_eq!(select(1 << 63 | 1 << 40, 1), 63);
+    }
+
+    #[test]
+    fn test_partial_round_trip() {
+        assert_eq!(partial_permutations(16, 3), Some(16 * 15 * 14));
+        assert_eq!(partial_permutations(64, 64), None);
+        for cells in [5, 16, 64] {
+            let count = partial_permutations(cells, 3).unwrap();
+            let mut previous = vec![];
+            for rank in (0..count).step_by(count as usize / 500 + 1) {
+                let mut positions = [0; 3];
+                unrank_partial(rank, cells, &mut positions);
+                assert!(positions.iter().all(|&p| (p as usize) < cells));
+                assert_eq!(rank_partial(&positions, cells), rank);
+                // ranks follow the lexicographic order of the positions
+                assert!(previous.as_slice() < positions.as_slice());
+                previous = positions.to_vec();
+            }
+        }
+    }
+
+    #[test]
+    fn test_rank_round_trip() {
+        assert_eq!(GameState::PERMUTATIONS, 20_922_789_888_000);
+        assert_eq!(GameState::default().rank(), Some(0));
+        assert_eq!(GameState::unrank(0), Some(GameState::default()));
+        assert_eq!(GameState::unrank(GameState::PERMUTATIONS), None);
+
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..1000 {
+            let rank = rng.next() % GameState::PERMUTATIONS;
+            let state = GameState::unrank(rank).unwrap();
+            assert!(state.all_tiles_unique());
+            assert_eq!(state.rank(), Some(rank));
+        }
+
+        // random walks reach states the other way around
+        let mut state = GameState::default();
+        for _ in 0..1000 {
+            state.perform_move(Move::ALL[rng.below(4)]);
+            let rank = state.rank().unwrap();
+            assert_eq!(GameState::unrank(rank), Some(state.clone()));
+        }
+
+        let mut invalid = GameState::default();
+        invalid.set(0, 0, Some(2));
+        assert_eq!(invalid.rank(), None);
+        // a duplicate is caught even when it takes the place of the blank
+        let mut duplicate = Board::<2, 2>::default();
+        duplicate.set(1, 1, Some(1));
+        assert_eq!(duplicate.rank(), None);
+        assert_eq!(duplicate.pattern_rank(&[2, 3]), None);
+    }
+
+    #[test]
+    fn test_rank_other_sizes() {
+        // every state of a small board gets its own rank
+        let mut seen = vec![false; Board::<2, 3>::PERMUTATIONS as usize];
+        for rank in 0..Board::<2, 3>::PERMUTATIONS {
+            let state = Board::<2, 3>::unrank(rank).unwrap();
+            let rank = state.rank().unwrap();
+            assert!(!seen[rank as usize]);
+            seen[rank as usize] = true;
+        }
+
+        let mut rng = Rng(88172645463325252);
+        for _ in 0..200 {
+            let rank = rng.next() % Board::<3, 3>::PERMUTATIONS;
+            assert_eq!(Board::<3, 3>::unrank(rank).unwrap().rank(), Some(rank));
+            let rank = rng.next() % Board::<4, 5>::PERMUTATIONS;
+            assert_eq!(Board::<4, 5>::unrank(rank).unwrap().rank(), Some(rank));
+        }
+        let last = Board::<4, 5>::PERMUTATIONS - 1;
+        assert_eq!(Board::<4, 5>::unrank(last).unwrap().rank(), Some(last));
+    }
+
+    #[test]
+    fn test_pattern_rank() {
+        let state = GameState::default();
+        assert_eq!(GameState::pattern_count(3), Some(16 * 15 * 14));
+        assert_eq!(state.pattern_rank(&[1, 2, 3]), Some(0));
+        // tiles 15, 14 and 13 sit in cells 14, 13 and 12
+        let rank = state.pattern_rank(&[15, 14, 13]).unwrap();
+        assert_eq!(rank, (14 * 15 + 13) * 14 + 12);
+        assert_eq!(
+            GameState::pattern_unrank(3, rank),
+            Some(vec![(2, 3), (1, 3), (0, 3)])
+        );
+        assert_eq!(GameState::pattern_unrank(3, 16 * 15 * 14), None);
+
+        assert_eq!(state.pattern_rank(&[1, 1]), None);
+        assert_eq!(state.pattern_rank(&[16]), None);
+        let mut missing = GameState::default();
+        missing.set(0, 0, None);
+        assert_eq!(missing.pattern_rank(&[1]), None);
+
+        // patterns of larger boards, as long as they fit into 64 cells
+        let board = Board::<8, 8>::default();
+        let rank = board.pattern_rank(&[63, 1, 2, 3, 4, 5]).unwrap();
+        let positions = Board::<8, 8>::pattern_unrank(6, rank).unwrap();
+        assert_eq!(positions[0], (6, 7));
+        assert_eq!(positions[5], (4, 0));
+        assert_eq!(Board::<9, 9>::default().pattern_rank(&[1]), None);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{Board, Move};
+
+/// SplitMix64, small and fast, and every seed (even 0) gives a good sequence. The scrambles
+/// only need to be reproducible, not unpredictable.
+pub(crate) struct Rng(pub(crate) u64);
+
+impl Rng {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`. Takes the high bits of the product, the bias is far too small to
+    /// matter for boards.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        ((self.next() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+impl<const W: usize, const H: usize> Board<W, H> {
+    /// Starts at the default state and makes `len` random moves, never undoing the previous one.
+    /// Returns the state together with the moves that lead to it. The same seed always gives the
+    /// same walk.
+    /// Boards one cell wide only allow moving along the line, so there the walk stops early
+    /// when the blank reaches the end.
+    pub fn random_walk(len: usize, seed: u64) -> (Self, Vec<Move>) {
+        let mut rng = Rng(seed);
+        let mut state = Self::default();
+        let mut moves: Vec<Move> = Vec::with_capacity(len);
+        while moves.len() < len {
+            let legal: Vec<Move> = state
+                .legal_moves()
+                .filter(|&m| moves.last() != Some(&m.inverse()))
+                .collect();
+            if legal.is_empty() {
+                break;
+            }
+            let m = legal[rng.below(legal.len())];
+            state.perform_move(m);
+            moves.push(m);
+        }
+        (state, moves)
+    }
+
+    /// A state drawn uniformly from all the solvable ones. The same seed always gives the same
+    /// state.
+    pub fn random_solvable(seed: u64) -> Self {
+        let mut rng = Rng(seed);
+        let mut tiles: Vec<Option<u8>> = (1..=Self::MAX_TILE).map(Some).chain([None]).collect();
+        if W == 1 || H == 1 {
+            // the tiles can't pass each other, only the blank moves
+            tiles.pop();
+            tiles.insert(rng.below(W * H), None);
+            return Self::from_tiles(&tiles);
+        }
+        // Fisher-Yates
+        for i in (1..tiles.len()).rev() {
+            tiles.swap(i, rng.below(i + 1));
+        }
+        let state = Self::from_tiles(&tiles);
+        if state.is_solvable() {
+            return state;
+        }
+        // Swapping two tiles flips the parity. Always swapping the first two pairs up every
+        // unsolvable state with exactly one solvable state, which keeps the result uniform.
+        let mut cells = (0..tiles.len()).filter(|&i| tiles[i].is_some());
+        let (a, b) = (cells.next().unwrap(), cells.next().unwrap());
+        tiles.swap(a, b);
+        Self::from_tiles(&tiles)
+    }
+
+    /// Builds a board from its cells in row-major order.
+    fn from_tiles(tiles: &[Option<u8>]) -> Self {
+        let mut board = [[None; H]; W];
+        for (i, &tile) in tiles.iter().enumerate() {
+            board[i % W][i / W] = tile;
+        }
+        Self::from_board(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameState;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_random_walk() {
+        let (state, moves) = GameState::random_walk(50, 7);
+        assert_eq!(moves.len(), 50);
+        assert_eq!(
+            GameState::random_walk(50, 7),
+            (state.clone(), moves.clone())
+        );
+        assert_ne!(GameState::random_walk(50, 8).1, moves);
+        // a shorter walk with the same seed is the start of the longer one
+        assert_eq!(GameState::random_walk(20, 7).1, moves[..20]);
+
+        assert!(moves.windows(2).all(|pair| pair[1] != pair[0].inverse()));
+        let mut replay = GameState::default();
+        assert_eq!(replay.perform_moves(&moves), moves.len());
+        assert_eq!(replay, state);
+
+        let (state, moves) = Board::<3, 5>::random_walk(40, 0);
+        let mut replay = Board::<3, 5>::default();
+        assert_eq!(replay.perform_moves(&moves), 40);
+        assert_eq!(replay, state);
+
+        // a single row only allows walking to the other end
+        let (state, moves) = Board::<4, 1>::random_walk(10, 3);
+        assert_eq!(moves, vec![Move::LeftToRight; 3]);
+        assert_eq!(state.blank(), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_random_solvable() {
+        for seed in 0..200 {
+            let state = GameState::random_solvable(seed);
+            assert!(state.all_tiles_unique());
+            assert!(state.is_solvable());
+            assert_eq!(GameState::random_solvable(seed), state);
+            assert!(Board::<3, 3>::random_solvable(seed).is_solvable());
+            assert!(Board::<2, 5>::random_solvable(seed).is_solvable());
+            assert!(Board::<1, 4>::random_solvable(seed).is_solvable());
+        }
+        assert_ne!(GameState::random_solvable(1), GameState::random_solvable(2));
+    }
+
+    #[test]
+    fn test_random_solvable_is_uniform() {
+        // the 2x2 board has 12 solvable states
+        let mut counts = HashMap::new();
+        for seed in 0..12000 {
+            *counts
+                .entry(Board::<2, 2>::random_solvable(seed))
+                .or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 12);
+        assert!(counts.values().all(|&count| (850..1150).contains(&count)));
+    }
+}
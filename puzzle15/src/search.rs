@@ -0,0 +1,1422 @@
+use crate::goal::Goal;
+use crate::pdb::AdditivePdb;
+use crate::{Board, GameState, Move, PackedBoard, PackedState, MAX_DEPTH};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// How a search for a path between two states ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveOutcome<const W: usize = 4, const H: usize = 4> {
+    /// A shortest path, empty if start and goal are the same state.
+    Solved(Vec<Move>),
+    /// There is no path, because the states have different parities or are not valid boards.
+    Unsolvable,
+    /// The search gave up before reaching the goal. `closest` is the explored state with the
+    /// smallest Manhattan `distance` to the goal and `path` leads there from the start.
+    BudgetExhausted {
+        closest: Board<W, H>,
+        path: Vec<Move>,
+        distance: u32,
+    },
+    /// There is no path of at most this many moves, the search did not look for longer ones.
+    NoPathWithin(u64),
+}
+
+/// Counters collected while searching.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolveStats {
+    /// number of states whose neighbours were generated
+    pub nodes_expanded: u64,
+    /// largest number of states waiting to be expanded at the same time, for the depth first
+    /// searches this is the longest path that was followed
+    pub peak_frontier: usize,
+    /// number of questions asked to an SMT solver, only the bounded model checker asks any
+    pub solver_queries: u64,
+    /// number of steps the circuit was unrolled by the bounded model checker
+    pub unroll_depth: u64,
+    pub elapsed: Duration,
+}
+
+/// Result of a solver together with statistics about the search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolveReport<const W: usize = 4, const H: usize = 4> {
+    pub outcome: SolveOutcome<W, H>,
+    pub stats: SolveStats,
+}
+
+impl<const W: usize, const H: usize> SolveReport<W, H> {
+    pub(crate) fn new(outcome: SolveOutcome<W, H>, mut stats: SolveStats, start: Instant) -> Self {
+        stats.elapsed = start.elapsed();
+        SolveReport { outcome, stats }
+    }
+
+    /// Returns the moves if a path was found.
+    pub fn path(&self) -> Option<&[Move]> {
+        match &self.outcome {
+            SolveOutcome::Solved(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Returns the moves if a path was found.
+    pub fn into_path(self) -> Option<Vec<Move>> {
+        match self.outcome {
+            SolveOutcome::Solved(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    pub fn is_solved(&self) -> bool {
+        matches!(self.outcome, SolveOutcome::Solved(_))
+    }
+}
+
+/// Answers the two questions every solver asks before searching: is there anything to do, and
+/// is there a path at all?
+pub(crate) fn trivial_report<const W: usize, const H: usize>(
+    from: &Board<W, H>,
+    goal: &Goal<W, H>,
+    start: Instant,
+) -> Option<SolveReport<W, H>> {
+    let outcome = if goal.is_reached(from) {
+        SolveOutcome::Solved(vec![])
+    } else if !goal.is_reachable_from(from) {
+        SolveOutcome::Unsolvable
+    } else {
+        return None;
+    };
+    Some(SolveReport::new(outcome, SolveStats::default(), start))
+}
+
+/// Row-major index of the tile that slides into the blank at `blank` when performing `m`.
+pub(crate) fn move_source<const W: usize, const H: usize>(blank: usize, m: Move) -> Option<usize> {
+    let (column, row) = (blank % W, blank / W);
+    match m {
+        Move::LeftToRight if column > 0 => Some(blank - 1),
+        Move::RightToLeft if column + 1 < W => Some(blank + 1),
+        Move::TopToBottom if row > 0 => Some(blank - W),
+        Move::BottomToTop if row + 1 < H => Some(blank + W),
+        _ => None,
+    }
+}
+
+/// How the searches store the states they visit. Boards of up to 16 cells are packed into a
+/// single word, larger ones are searched as they are. The searches only run on states that
+/// passed the solvability check, so every state has valid tiles and a blank.
+pub(crate) trait SearchState<const W: usize, const H: usize>: Clone + Eq + Hash {
+    fn pack(board: &Board<W, H>) -> Self;
+
+    fn unpack(&self) -> Board<W, H>;
+
+    /// Returns the tile at the row-major cell `index`, 0 for the blank.
+    fn tile(&self, index: usize) -> u8;
+
+    /// Row-major index of the blank.
+    fn blank_index(&self) -> usize;
+
+    /// Moves the tile at `from` into the empty cell at `blank`.
+    fn slide(&mut self, from: usize, blank: usize);
+
+    /// Returns the state after performing `m`, or None if the move is not possible.
+    fn apply(&self, m: Move) -> Option<Self> {
+        let blank = self.blank_index();
+        let from = move_source::<W, H>(blank, m)?;
+        let mut next = self.clone();
+        next.slide(from, blank);
+        Some(next)
+    }
+}
+
+impl<const W: usize, const H: usize> SearchState<W, H> for PackedBoard<W, H> {
+    fn pack(board: &Board<W, H>) -> Self {
+        PackedBoard::try_from(board).expect("solvable boards of up to 16 cells can be packed")
+    }
+
+    fn unpack(&self) -> Board<W, H> {
+        Board::from(*self)
+    }
+
+    fn tile(&self, index: usize) -> u8 {
+        PackedBoard::tile(*self, index)
+    }
+
+    fn blank_index(&self) -> usize {
+        PackedBoard::blank_index(*self)
+    }
+
+    fn slide(&mut self, from: usize, blank: usize) {
+        PackedBoard::slide(self, from, blank)
+    }
+}
+
+impl<const W: usize, const H: usize> SearchState<W, H> for Board<W, H> {
+    fn pack(board: &Board<W, H>) -> Self {
+        board.clone()
+    }
+
+    fn unpack(&self) -> Board<W, H> {
+        self.clone()
+    }
+
+    fn tile(&self, index: usize) -> u8 {
+        self.board[index % W][index / W].unwrap_or(0)
+    }
+
+    fn blank_index(&self) -> usize {
+        let (x, y) = self.blank.expect("solvable boards have a blank");
+        y as usize * W + x as usize
+    }
+
+    fn slide(&mut self, from: usize, blank: usize) {
+        self.swap(
+            (from % W) as u8,
+            (from / W) as u8,
+            (blank % W) as u8,
+            (blank / W) as u8,
+        );
+    }
+}
+
+/// Estimates the number of moves left by summing up how far every tile is away from the
+/// position it has in the goal state. Tiles the goal doesn't care about count as being in place.
+pub(crate) struct Manhattan<const W: usize, const H: usize> {
+    /// distance of every tile at every row-major cell to its goal, at `tile * W * H + cell`
+    distances: Vec<u8>,
+}
+
+impl<const W: usize, const H: usize> Manhattan<W, H> {
+    pub(crate) fn new(goal: &Board<W, H>) -> Self {
+        Self::for_goal(&Goal::from(goal))
+    }
+
+    pub(crate) fn for_goal(goal: &Goal<W, H>) -> Self {
+        let cells = W * H;
+        let mut distances = vec![0; cells * cells];
+        for (goal_index, tile) in goal.required() {
+            let tile = tile as usize;
+            if tile == 0 || tile >= cells {
+                continue;
+            }
+            let (x, y) = (goal_index % W, goal_index / W);
+            for index in 0..cells {
+                let (column, row) = (index % W, index / W);
+                distances[tile * cells + index] = (column.abs_diff(x) + row.abs_diff(y)) as u8;
+            }
+        }
+        Manhattan { distances }
+    }
+
+    pub(crate) fn estimate(&self, state: &impl SearchState<W, H>) -> u32 {
+        let cells = W * H;
+        let mut distance = 0;
+        for index in 0..cells {
+            distance += self.distances[state.tile(index) as usize * cells + index] as u32;
+        }
+        distance
+    }
+}
+
+/// A state that was reached during the search together with how we got there.
+struct Node<S> {
+    state: S,
+    cost: u32,
+    parent: Option<(usize, Move)>,
+}
+
+/// Walks the parent links back to the start and returns the moves in the order they were made.
+fn reconstruct_path<S>(nodes: &[Node<S>], mut index: usize) -> Vec<Move> {
+    let mut path = vec![];
+    while let Some((parent, m)) = nodes[index].parent {
+        path.push(m);
+        index = parent;
+    }
+    path.reverse();
+    path
+}
+
+/// Finds the minimal number of moves needed to get from one state to the other using A* search
+/// guided by the Manhattan distance to `to`.
+/// Because the heuristic never overestimates, the returned path is always a shortest one.
+/// Unlike `find_shortest_path` there is no limit on the number of states explored.
+pub fn find_shortest_path_astar<const W: usize, const H: usize>(
+    from: Board<W, H>,
+    to: Board<W, H>,
+) -> SolveReport<W, H> {
+    find_shortest_path_astar_to_goal(from, &Goal::from(&to))
+}
+
+/// Same as `find_shortest_path_astar`, but stops at the first state that reaches `goal`. The
+/// Manhattan distance only counts the tiles the goal asks for.
+pub fn find_shortest_path_astar_to_goal<const W: usize, const H: usize>(
+    from: Board<W, H>,
+    goal: &Goal<W, H>,
+) -> SolveReport<W, H> {
+    let heuristic = Manhattan::for_goal(goal);
+    if PackedBoard::<W, H>::FITS {
+        astar(from, goal, |state: &PackedBoard<W, H>| {
+            heuristic.estimate(state)
+        })
+    } else {
+        astar(from, goal, |state: &Board<W, H>| heuristic.estimate(state))
+    }
+}
+
+/// Same as `find_shortest_path_astar`, but uses the pattern databases when they give a better
+/// estimate than the Manhattan distance. The databases are built for the 15-puzzle.
+/// They only apply to goals that have the blank in the bottom right corner, like
+/// `GameState::default()`. For all other goals this behaves like `find_shortest_path_astar`.
+pub fn find_shortest_path_astar_pdb(
+    from: GameState,
+    to: GameState,
+    pdb: &AdditivePdb,
+) -> SolveReport {
+    let heuristic = Manhattan::new(&to);
+    let goal = PackedState::try_from(&to)
+        .ok()
+        .filter(|goal| goal.tile(15) == 0);
+    astar(from, &Goal::from(&to), |state: &PackedState| {
+        let estimate = heuristic.estimate(state);
+        match goal {
+            Some(goal) => estimate.max(pdb_estimate(pdb, *state, goal)),
+            None => estimate,
+        }
+    })
+}
+
+fn astar<const W: usize, const H: usize, S: SearchState<W, H>>(
+    from: Board<W, H>,
+    goal: &Goal<W, H>,
+    estimate: impl Fn(&S) -> u32,
+) -> SolveReport<W, H> {
+    let start = Instant::now();
+    if let Some(report) = trivial_report(&from, goal, start) {
+        return report;
+    }
+    let mut stats = SolveStats::default();
+    let from = S::pack(&from);
+
+    // all states we ever generated, the open list refers to them by index
+    let h = estimate(&from);
+    let mut nodes = vec![Node {
+        state: from.clone(),
+        cost: 0,
+        parent: None,
+    }];
+    // cheapest known cost to reach a state
+    let mut best_cost: HashMap<S, u32> = HashMap::new();
+    best_cost.insert(from, 0);
+    // ordered by (estimated total cost, estimated remaining cost), ties on the total are broken
+    // in favour of states that are closer to the goal
+    let mut open = BinaryHeap::new();
+    open.push(Reverse((h, h, 0usize)));
+
+    while let Some(Reverse((_, _, index))) = open.pop() {
+        let (state, cost) = (nodes[index].state.clone(), nodes[index].cost);
+        // skip stale entries for which a cheaper path was found after they were queued
+        if best_cost.get(&state).is_some_and(|&best| best < cost) {
+            continue;
+        }
+        if goal.is_reached_by(&state) {
+            let path = reconstruct_path(&nodes, index);
+            return SolveReport::new(SolveOutcome::Solved(path), stats, start);
+        }
+        stats.nodes_expanded += 1;
+        for m in Move::ALL {
+            let Some(next) = state.apply(m) else {
+                continue;
+            };
+            let next_cost = cost + 1;
+            if best_cost.get(&next).is_some_and(|&best| best <= next_cost) {
+                continue;
+            }
+            best_cost.insert(next.clone(), next_cost);
+            let h = estimate(&next);
+            open.push(Reverse((next_cost + h, h, nodes.len())));
+            nodes.push(Node {
+                state: next,
+                cost: next_cost,
+                parent: Some((index, m)),
+            });
+        }
+        stats.peak_frontier = stats.peak_frontier.max(open.len());
+    }
+
+    // unreachable for solvable states
+    SolveReport::new(SolveOutcome::Unsolvable, stats, start)
+}
+
+/// One direction of the bidirectional search.
+struct Frontier<S> {
+    nodes: Vec<Node<S>>,
+    seen: HashMap<S, usize>,
+    /// the nodes that were reached last, all at the same distance from the start
+    layer: Vec<usize>,
+}
+
+impl<S: Clone + Eq + Hash> Frontier<S> {
+    fn new(start: S) -> Self {
+        Frontier {
+            nodes: vec![Node {
+                state: start.clone(),
+                cost: 0,
+                parent: None,
+            }],
+            seen: HashMap::from([(start, 0)]),
+            layer: vec![0],
+        }
+    }
+}
+
+/// Finds the minimal number of moves needed to get from one state to the other by searching
+/// breadth first from both ends at the same time until the two searches meet.
+/// Both searches only need to go half as deep, so this reaches about twice the depth of
+/// `find_shortest_path` with the same `MAX_DEPTH` budget of states.
+pub fn find_shortest_path_bidirectional<const W: usize, const H: usize>(
+    from: Board<W, H>,
+    to: Board<W, H>,
+) -> SolveReport<W, H> {
+    if PackedBoard::<W, H>::FITS {
+        bidirectional::<W, H, PackedBoard<W, H>>(from, to)
+    } else {
+        bidirectional::<W, H, Board<W, H>>(from, to)
+    }
+}
+
+fn bidirectional<const W: usize, const H: usize, S: SearchState<W, H>>(
+    from: Board<W, H>,
+    to: Board<W, H>,
+) -> SolveReport<W, H> {
+    let start = Instant::now();
+    if let Some(report) = trivial_report(&from, &Goal::from(&to), start) {
+        return report;
+    }
+    let mut stats = SolveStats::default();
+    let heuristic = Manhattan::new(&to);
+    let (from, to) = (S::pack(&from), S::pack(&to));
+    // (distance to the goal, index in the forward search)
+    let mut closest = (heuristic.estimate(&from), 0);
+    let mut forward = Frontier::new(from);
+    let mut backward = Frontier::new(to);
+
+    loop {
+        if forward.nodes.len() + backward.nodes.len() > MAX_DEPTH {
+            let (distance, index) = closest;
+            let outcome = SolveOutcome::BudgetExhausted {
+                closest: forward.nodes[index].state.unpack(),
+                path: reconstruct_path(&forward.nodes, index),
+                distance,
+            };
+            return SolveReport::new(outcome, stats, start);
+        }
+
+        // grow the smaller side by one full layer, the first layer in which the searches meet
+        // contains a shortest path, but not necessarily at the first meeting point
+        let is_forward = forward.layer.len() <= backward.layer.len();
+        let (side, other) = if is_forward {
+            (&mut forward, &backward)
+        } else {
+            (&mut backward, &forward)
+        };
+        // (total length, index on this side, index on the other side)
+        let mut best: Option<(u32, usize, usize)> = None;
+        let mut next_layer = vec![];
+        for &index in &side.layer {
+            stats.nodes_expanded += 1;
+            let (state, cost) = (side.nodes[index].state.clone(), side.nodes[index].cost);
+            for m in Move::ALL {
+                let Some(next) = state.apply(m) else {
+                    continue;
+                };
+                if side.seen.contains_key(&next) {
+                    continue;
+                }
+                let next_index = side.nodes.len();
+                if let Some(&other_index) = other.seen.get(&next) {
+                    let total = cost + 1 + other.nodes[other_index].cost;
+                    if best.is_none_or(|(shortest, _, _)| total < shortest) {
+                        best = Some((total, next_index, other_index));
+                    }
+                }
+                if is_forward {
+                    let distance = heuristic.estimate(&next);
+                    if distance < closest.0 {
+                        closest = (distance, next_index);
+                    }
+                }
+                side.seen.insert(next.clone(), next_index);
+                side.nodes.push(Node {
+                    state: next,
+                    cost: cost + 1,
+                    parent: Some((index, m)),
+                });
+                next_layer.push(next_index);
+            }
+        }
+        side.layer = next_layer;
+        stats.peak_frontier = stats
+            .peak_frontier
+            .max(forward.layer.len() + backward.layer.len());
+
+        if let Some((_, side_index, other_index)) = best {
+            let (forward_index, backward_index) = if is_forward {
+                (side_index, other_index)
+            } else {
+                (other_index, side_index)
+            };
+            // the backward search went from the goal to the meeting point, so its moves have to
+            // be undone in reverse order
+            let mut path = reconstruct_path(&forward.nodes, forward_index);
+            let back = reconstruct_path(&backward.nodes, backward_index);
+            path.extend(back.into_iter().rev().map(Move::inverse));
+            return SolveReport::new(SolveOutcome::Solved(path), stats, start);
+        }
+        if forward.layer.is_empty() || backward.layer.is_empty() {
+            // unreachable for solvable states
+            return SolveReport::new(SolveOutcome::Unsolvable, stats, start);
+        }
+    }
+}
+
+/// Looks up the pattern databases for `state`. The databases are built for
+/// `GameState::default()`, so the tiles are renamed after the default tile at their position in
+/// `goal`, which needs to have the blank in the bottom right corner.
+fn pdb_estimate(pdb: &AdditivePdb, state: PackedState, goal: PackedState) -> u32 {
+    let mut positions = [0; 16];
+    for index in 0..16 {
+        positions[state.tile(index) as usize] = index as u8;
+    }
+    pdb.estimate_positions(&positions, |tile| goal.tile(tile as usize - 1))
+}
+
+/// Length of the longest strictly increasing subsequence of a (very short) sequence of at most
+/// `N` values.
+fn longest_increasing<const N: usize>(values: &[usize]) -> usize {
+    let mut lengths = [1; N];
+    for i in 0..values.len() {
+        for j in 0..i {
+            if values[j] < values[i] {
+                lengths[i] = lengths[i].max(lengths[j] + 1);
+            }
+        }
+    }
+    lengths[..values.len()].iter().copied().max().unwrap_or(0)
+}
+
+/// Search state of the iterative deepening search. Moves are applied in place and the heuristic
+/// is updated incrementally, so expanding a node only touches the tile that moved.
+struct Ida<'a, const W: usize, const H: usize, S> {
+    board: S,
+    /// row-major index of every tile, the inverse of `board`
+    positions: Vec<u8>,
+    blank: usize,
+    /// row-major goal index of every tile, `FREE` for the ones the goal doesn't care about
+    goal: Vec<usize>,
+    manhattan: u32,
+    /// extra moves caused by linear conflicts in every row and column
+    row_conflicts: [u32; H],
+    column_conflicts: [u32; W],
+    /// pattern databases together with the goal they are used for, only on the 15-puzzle
+    pdb: Option<(&'a AdditivePdb, S)>,
+    path: Vec<Move>,
+    stats: SolveStats,
+    /// number of nodes to expand before giving up
+    budget: u64,
+    /// set once the budget ran out
+    exhausted: bool,
+    /// the state with the smallest Manhattan distance seen so far, with that distance and the
+    /// path to it
+    closest: (u32, S, Vec<Move>),
+}
+
+/// Goal index of the tiles that may end up anywhere.
+const FREE: usize = usize::MAX;
+
+impl<'a, const W: usize, const H: usize, S: SearchState<W, H>> Ida<'a, W, H, S> {
+    fn new(board: S, goal_cells: &Goal<W, H>) -> Self {
+        let mut goal = vec![FREE; W * H];
+        for (index, tile) in goal_cells.required() {
+            goal[tile as usize] = index;
+        }
+        let mut positions = vec![0; W * H];
+        for index in 0..W * H {
+            positions[board.tile(index) as usize] = index as u8;
+        }
+        let mut search = Ida {
+            board: board.clone(),
+            blank: positions[0] as usize,
+            positions,
+            goal,
+            manhattan: 0,
+            row_conflicts: [0; H],
+            column_conflicts: [0; W],
+            pdb: None,
+            path: vec![],
+            stats: SolveStats::default(),
+            budget: u64::MAX,
+            exhausted: false,
+            closest: (u32::MAX, board.clone(), vec![]),
+        };
+        for index in 0..W * H {
+            search.manhattan += search.tile_distance(index);
+        }
+        search.closest.0 = search.manhattan;
+        for row in 0..H {
+            search.row_conflicts[row] = search.count_row_conflicts(row);
+        }
+        for column in 0..W {
+            search.column_conflicts[column] = search.count_column_conflicts(column);
+        }
+        search
+    }
+
+    /// Manhattan distance of the tile at `index` to its goal, 0 for the blank and free tiles.
+    fn tile_distance(&self, index: usize) -> u32 {
+        let tile = self.board.tile(index);
+        let goal = self.goal[tile as usize];
+        if tile == 0 || goal == FREE {
+            return 0;
+        }
+        ((index % W).abs_diff(goal % W) + (index / W).abs_diff(goal / W)) as u32
+    }
+
+    /// Two tiles that are both in their goal row, but in the wrong order, are in conflict: one of
+    /// them has to leave the row and come back, costing two moves on top of the Manhattan
+    /// distance. Every tile that is not part of the longest correctly ordered run has to do so.
+    fn count_row_conflicts(&self, row: usize) -> u32 {
+        let mut goal_columns = [0; W];
+        let mut len = 0;
+        for column in 0..W {
+            let tile = self.board.tile(row * W + column);
+            let goal = self.goal[tile as usize];
+            if tile != 0 && goal != FREE && goal / W == row {
+                goal_columns[len] = goal % W;
+                len += 1;
+            }
+        }
+        2 * (len - longest_increasing::<W>(&goal_columns[..len])) as u32
+    }
+
+    /// Same as `count_row_conflicts`, but for tiles in their goal column.
+    fn count_column_conflicts(&self, column: usize) -> u32 {
+        let mut goal_rows = [0; H];
+        let mut len = 0;
+        for row in 0..H {
+            let tile = self.board.tile(row * W + column);
+            let goal = self.goal[tile as usize];
+            if tile != 0 && goal != FREE && goal % W == column {
+                goal_rows[len] = goal / W;
+                len += 1;
+            }
+        }
+        2 * (len - longest_increasing::<H>(&goal_rows[..len])) as u32
+    }
+
+    /// Manhattan distance plus linear conflicts or the pattern databases, whichever is larger.
+    /// Never overestimates the remaining moves.
+    fn estimate(&self) -> u32 {
+        let estimate = self.manhattan
+            + self.row_conflicts.iter().sum::<u32>()
+            + self.column_conflicts.iter().sum::<u32>();
+        match &self.pdb {
+            Some((pdb, goal)) => estimate
+                .max(pdb.estimate_positions(&self.positions, |tile| goal.tile(tile as usize - 1))),
+            None => estimate,
+        }
+    }
+
+    /// Moves a tile into the blank. Returns false if the move is not possible.
+    fn apply(&mut self, m: Move) -> bool {
+        let (column, row) = (self.blank % W, self.blank / W);
+        let Some(tile) = move_source::<W, H>(self.blank, m) else {
+            return false;
+        };
+        self.manhattan -= self.tile_distance(tile);
+        self.board.slide(tile, self.blank);
+        self.positions[self.board.tile(self.blank) as usize] = self.blank as u8;
+        self.manhattan += self.tile_distance(self.blank);
+        // the moved tile keeps its position relative to the other tiles of the line it moves
+        // along, so only the two lines it leaves and enters can change
+        if tile / W == row {
+            self.column_conflicts[tile % W] = self.count_column_conflicts(tile % W);
+            self.column_conflicts[column] = self.count_column_conflicts(column);
+        } else {
+            self.row_conflicts[tile / W] = self.count_row_conflicts(tile / W);
+            self.row_conflicts[row] = self.count_row_conflicts(row);
+        }
+        self.blank = tile;
+        true
+    }
+
+    /// Depth first search that gives up on every path whose estimated total cost exceeds
+    /// `bound`. Returns Ok once the goal was reached (the moves are in `self.path`) or the
+    /// smallest estimate that was over the bound.
+    fn search(&mut self, cost: u32, bound: u32) -> Result<(), u32> {
+        let estimate = cost + self.estimate();
+        if estimate > bound {
+            return Err(estimate);
+        }
+        // every tile is in place, and with them the blank unless the goal leaves cells open
+        if self.manhattan == 0 && (self.goal[0] == FREE || self.goal[0] == self.blank) {
+            return Ok(());
+        }
+        if self.manhattan < self.closest.0 {
+            self.closest = (self.manhattan, self.board.clone(), self.path.clone());
+        }
+        if self.stats.nodes_expanded >= self.budget {
+            self.exhausted = true;
+            return Err(u32::MAX);
+        }
+        self.stats.nodes_expanded += 1;
+        self.stats.peak_frontier = self.stats.peak_frontier.max(self.path.len() + 1);
+        let mut next_bound = u32::MAX;
+        for m in Move::ALL {
+            // going straight back can never be part of a shortest path
+            if self.path.last() == Some(&m.inverse()) || !self.apply(m) {
+                continue;
+            }
+            self.path.push(m);
+            match self.search(cost + 1, bound) {
+                Ok(()) => return Ok(()),
+                Err(_) if self.exhausted => return Err(u32::MAX),
+                Err(over) => next_bound = next_bound.min(over),
+            }
+            self.path.pop();
+            self.apply(m.inverse());
+        }
+        Err(next_bound)
+    }
+}
+
+/// Finds the minimal number of moves needed to get from one state to the other using iterative
+/// deepening A*, guided by the Manhattan distance plus linear conflicts.
+/// Only the current path is kept in memory, which makes it possible to solve positions that
+/// need 50 and more moves.
+pub fn find_shortest_path_idastar<const W: usize, const H: usize>(
+    from: Board<W, H>,
+    to: Board<W, H>,
+) -> SolveReport<W, H> {
+    find_shortest_path_idastar_to_goal(from, &Goal::from(&to))
+}
+
+/// Same as `find_shortest_path_idastar`, but stops at the first state that reaches `goal`. The
+/// heuristic only counts the tiles the goal asks for.
+pub fn find_shortest_path_idastar_to_goal<const W: usize, const H: usize>(
+    from: Board<W, H>,
+    goal: &Goal<W, H>,
+) -> SolveReport<W, H> {
+    if PackedBoard::<W, H>::FITS {
+        idastar::<W, H, PackedBoard<W, H>>(from, goal, None, u64::MAX)
+    } else {
+        idastar::<W, H, Board<W, H>>(from, goal, None, u64::MAX)
+    }
+}
+
+/// Same as `find_shortest_path_idastar`, but gives up after expanding `max_nodes` states. The
+/// state closest to `to` it reports is the one with the smallest Manhattan distance it saw.
+pub fn find_shortest_path_idastar_budget<const W: usize, const H: usize>(
+    from: Board<W, H>,
+    to: Board<W, H>,
+    max_nodes: u64,
+) -> SolveReport<W, H> {
+    let goal = Goal::from(&to);
+    if PackedBoard::<W, H>::FITS {
+        idastar::<W, H, PackedBoard<W, H>>(from, &goal, None, max_nodes)
+    } else {
+        idastar::<W, H, Board<W, H>>(from, &goal, None, max_nodes)
+    }
+}
+
+/// Same as `find_shortest_path_idastar`, but uses the pattern databases when they give a better
+/// estimate than Manhattan distance plus linear conflicts. The databases are built for the
+/// 15-puzzle.
+/// They only apply to goals that have the blank in the bottom right corner, like
+/// `GameState::default()`. For all other goals this behaves like `find_shortest_path_idastar`.
+pub fn find_shortest_path_idastar_pdb(
+    from: GameState,
+    to: GameState,
+    pdb: &AdditivePdb,
+) -> SolveReport {
+    idastar::<4, 4, PackedState>(from, &Goal::from(&to), Some(pdb), u64::MAX)
+}
+
+fn idastar<const W: usize, const H: usize, S: SearchState<W, H>>(
+    from: Board<W, H>,
+    goal: &Goal<W, H>,
+    pdb: Option<&AdditivePdb>,
+    budget: u64,
+) -> SolveReport<W, H> {
+    let start = Instant::now();
+    if let Some(report) = trivial_report(&from, goal, start) {
+        return report;
+    }
+    let mut search = Ida::new(S::pack(&from), goal);
+    search.budget = budget;
+    // only ever given for the 15-puzzle, and the databases need a complete goal
+    search.pdb = pdb
+        .zip(goal.state())
+        .map(|(pdb, goal)| (pdb, S::pack(&goal)))
+        .filter(|(_, goal)| goal.tile(15) == 0);
+    let mut bound = search.estimate();
+    loop {
+        let outcome = match search.search(0, bound) {
+            Ok(()) => SolveOutcome::Solved(search.path),
+            Err(_) if search.exhausted => {
+                let (distance, closest, path) = search.closest;
+                SolveOutcome::BudgetExhausted {
+                    closest: closest.unpack(),
+                    path,
+                    distance,
+                }
+            }
+            // no move possible at all, cannot happen on a board with at least two cells
+            Err(u32::MAX) => SolveOutcome::Unsolvable,
+            Err(next_bound) => {
+                bound = next_bound;
+                continue;
+            }
+        };
+        return SolveReport::new(outcome, search.stats, start);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_shortest_path, find_shortest_path_to_goal, GoalCell};
+
+    #[test]
+    fn test_manhattan() {
+        let goal = GameState::default();
+        let heuristic = Manhattan::new(&goal);
+        assert_eq!(heuristic.estimate(&goal), 0);
+
+        let mut state = GameState::default();
+        state.perform_moves(&[Move::TopToBottom, Move::LeftToRight]);
+        assert_eq!(heuristic.estimate(&state), 2);
+
+        // tiles that are far away count with their full distance
+        let mut state = GameState::default();
+        state.swap(0, 0, 3, 2);
+        assert_eq!(heuristic.estimate(&state), 10);
+    }
+
+    #[test]
+    fn test_astar_matches_bfs() {
+        let scrambles: [&[Move]; 4] = [
+            &[Move::TopToBottom, Move::TopToBottom, Move::TopToBottom],
+            &[
+                Move::TopToBottom,
+                Move::TopToBottom,
+                Move::LeftToRight,
+                Move::LeftToRight,
+                Move::BottomToTop,
+                Move::TopToBottom,
+                Move::BottomToTop,
+                Move::TopToBottom,
+            ],
+            &[
+                Move::TopToBottom,
+                Move::LeftToRight,
+                Move::TopToBottom,
+                Move::RightToLeft,
+            ],
+            &[
+                Move::LeftToRight,
+                Move::TopToBottom,
+                Move::LeftToRight,
+                Move::TopToBottom,
+                Move::RightToLeft,
+                Move::TopToBottom,
+                Move::LeftToRight,
+            ],
+        ];
+        for moves in scrambles {
+            let mut state = GameState::default();
+            state.perform_moves(moves);
+            let expected = find_shortest_path(GameState::default(), state.clone())
+                .into_path()
+                .unwrap();
+            let actual = find_shortest_path_astar(GameState::default(), state.clone())
+                .into_path()
+                .unwrap();
+            assert_eq!(actual.len(), expected.len());
+
+            let mut reached = GameState::default();
+            assert_eq!(reached.perform_moves(&actual), actual.len());
+            assert_eq!(reached, state);
+        }
+    }
+
+    #[test]
+    fn test_astar_deep_scramble() {
+        // far beyond what the plain breadth first search can reach
+        for seed in [1, 42, 1234] {
+            let (state, moves) = GameState::random_walk(30, seed);
+
+            let path = find_shortest_path_astar(GameState::default(), state.clone())
+                .into_path()
+                .unwrap();
+            assert!(!path.is_empty());
+            assert!(path.len() <= moves.len());
+            let mut reached = GameState::default();
+            assert_eq!(reached.perform_moves(&path), path.len());
+            assert_eq!(reached, state);
+
+            // solving in the other direction has to take exactly as many moves
+            let back = find_shortest_path_astar(state, GameState::default())
+                .into_path()
+                .unwrap();
+            assert_eq!(back.len(), path.len());
+        }
+    }
+
+    #[test]
+    fn test_bidirectional_matches_bfs() {
+        let mut states = vec![];
+        for seed in 1..=9 {
+            states.push(GameState::random_walk(seed as usize, seed * 7919).0);
+        }
+        let mut moved = GameState::default();
+        moved.perform_moves(&[Move::TopToBottom, Move::LeftToRight, Move::TopToBottom]);
+        states.push(moved);
+
+        for state in states {
+            let expected = find_shortest_path(GameState::default(), state.clone());
+            let expected = expected.into_path().unwrap();
+            let report = find_shortest_path_bidirectional(GameState::default(), state.clone());
+            let actual = report.into_path().unwrap();
+            assert_eq!(actual.len(), expected.len());
+
+            let mut reached = GameState::default();
+            assert_eq!(reached.perform_moves(&actual), actual.len());
+            assert_eq!(reached, state);
+
+            // and the other way around
+            let back = find_shortest_path_bidirectional(state.clone(), GameState::default());
+            assert_eq!(back.into_path().unwrap().len(), expected.len());
+        }
+    }
+
+    #[test]
+    fn test_bidirectional_deeper_than_bfs() {
+        let (state, _) = GameState::random_walk(20, 1);
+        let optimal = find_shortest_path_idastar(GameState::default(), state.clone())
+            .into_path()
+            .unwrap()
+            .len();
+        assert!(optimal >= 16, "scramble too easy: {optimal} moves");
+
+        let bfs = find_shortest_path(GameState::default(), state.clone());
+        assert!(matches!(bfs.outcome, SolveOutcome::BudgetExhausted { .. }));
+
+        let report = find_shortest_path_bidirectional(GameState::default(), state.clone());
+        let path = report.into_path().unwrap();
+        assert_eq!(path.len(), optimal);
+        let mut reached = GameState::default();
+        assert_eq!(reached.perform_moves(&path), path.len());
+        assert_eq!(reached, state);
+    }
+
+    #[test]
+    fn test_bidirectional_trivial() {
+        let state = GameState::default();
+        let report = find_shortest_path_bidirectional(state.clone(), state.clone());
+        assert_eq!(report.outcome, SolveOutcome::Solved(vec![]));
+
+        let mut swapped = GameState::default();
+        swapped.swap(1, 3, 2, 3);
+        let report = find_shortest_path_bidirectional(state, swapped);
+        assert_eq!(report.outcome, SolveOutcome::Unsolvable);
+
+        // far beyond the budget of both searches
+        let (far, _) = GameState::random_walk(200, 9);
+        let report = find_shortest_path_bidirectional(GameState::default(), far.clone());
+        let SolveOutcome::BudgetExhausted {
+            closest,
+            path,
+            distance,
+        } = report.outcome
+        else {
+            panic!("expected the search to run out of budget");
+        };
+        let mut reached = GameState::default();
+        reached.perform_moves(&path);
+        assert_eq!(reached, closest);
+        assert_eq!(distance, Manhattan::new(&far).estimate(&closest));
+    }
+
+    /// Solves a scramble of a `W` x `H` board with every solver and checks that they agree.
+    fn check_solvers<const W: usize, const H: usize>(len: usize, seed: u64, with_bfs: bool) {
+        let (state, moves) = Board::<W, H>::random_walk(len, seed);
+        let goal = Board::<W, H>::default();
+        let expected = find_shortest_path_idastar(state.clone(), goal.clone())
+            .into_path()
+            .unwrap();
+        assert!(expected.len() <= moves.len());
+        let mut reached = state.clone();
+        assert_eq!(reached.perform_moves(&expected), expected.len());
+        assert_eq!(reached, goal);
+
+        let mut reports = vec![
+            find_shortest_path_astar(state.clone(), goal.clone()),
+            find_shortest_path_bidirectional(state.clone(), goal.clone()),
+        ];
+        if with_bfs {
+            reports.push(find_shortest_path(state.clone(), goal.clone()));
+        }
+        for report in reports {
+            let path = report.into_path().unwrap();
+            assert_eq!(path.len(), expected.len());
+            let mut reached = state.clone();
+            assert_eq!(reached.perform_moves(&path), path.len());
+            assert_eq!(reached, goal);
+        }
+    }
+
+    #[test]
+    fn test_other_sizes() {
+        // packed into a word
+        check_solvers::<3, 3>(14, 5, true);
+        check_solvers::<2, 4>(14, 6, true);
+        check_solvers::<3, 5>(10, 7, true);
+        // searched as boards
+        check_solvers::<5, 5>(14, 8, false);
+        check_solvers::<6, 3>(14, 9, false);
+
+        // the tiles of a single row cannot pass each other
+        let mut row = Board::<4, 1>::default();
+        assert_eq!(
+            row.perform_moves(&[Move::LeftToRight, Move::LeftToRight]),
+            2
+        );
+        let report = find_shortest_path_idastar(row.clone(), Board::default());
+        assert_eq!(report.into_path().unwrap().len(), 2);
+        row.swap(0, 0, 3, 0);
+        let report = find_shortest_path_astar(row, Board::default());
+        assert_eq!(report.outcome, SolveOutcome::Unsolvable);
+    }
+
+    #[test]
+    fn test_astar_same_state() {
+        let state = GameState::default();
+        let report = find_shortest_path_astar(state.clone(), state);
+        assert_eq!(report.outcome, SolveOutcome::Solved(vec![]));
+        assert_eq!(report.stats.nodes_expanded, 0);
+    }
+
+    /// Builds a state from the tiles in row-major order, 0 being the blank.
+    fn from_row_major(tiles: [u8; 16]) -> GameState {
+        let mut state = GameState::default();
+        for (index, tile) in tiles.into_iter().enumerate() {
+            let tile = if tile == 0 { None } else { Some(tile) };
+            state.set((index % 4) as u8, (index / 4) as u8, tile);
+        }
+        state
+    }
+
+    #[test]
+    fn test_linear_conflict() {
+        let goal = PackedState::default();
+        assert_eq!(Ida::new(goal, &Goal::default()).estimate(), 0);
+        let tiles: [u8; 16] = std::array::from_fn(|index| goal.tile(index));
+
+        // 1 and 2 swapped in the top row: both are one step away and one has to go around
+        let mut swapped = tiles;
+        swapped.swap(0, 1);
+        let search = Ida::new(
+            PackedState::pack(&from_row_major(swapped)),
+            &Goal::default(),
+        );
+        assert_eq!(search.manhattan, 2);
+        assert_eq!(search.estimate(), 4);
+
+        // a fully reversed row needs three of its four tiles to step out of the way
+        let mut reversed = tiles;
+        reversed[..4].reverse();
+        let search = Ida::new(
+            PackedState::pack(&from_row_major(reversed)),
+            &Goal::default(),
+        );
+        assert_eq!(search.manhattan, 8);
+        assert_eq!(search.estimate(), 14);
+
+        // tiles in their goal column but in the wrong order conflict as well
+        let mut swapped = tiles;
+        swapped.swap(0, 4);
+        assert_eq!(
+            Ida::new(
+                PackedState::pack(&from_row_major(swapped)),
+                &Goal::default()
+            )
+            .estimate(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_incremental_estimate() {
+        let (_, moves) = GameState::random_walk(200, 7);
+        let goal = PackedState::default();
+        let mut search = Ida::new(goal, &Goal::default());
+        let mut state = GameState::default();
+        for m in moves {
+            assert!(search.apply(m));
+            assert!(state.perform_move(m));
+            let fresh = Ida::new(PackedState::pack(&state), &Goal::default());
+            assert_eq!(search.board, fresh.board);
+            assert_eq!(search.manhattan, fresh.manhattan);
+            assert_eq!(search.estimate(), fresh.estimate());
+        }
+    }
+
+    #[test]
+    fn test_idastar_matches_astar() {
+        for seed in [3, 99, 2024, 31337] {
+            let (state, _) = GameState::random_walk(40, seed);
+            let expected = find_shortest_path_astar(GameState::default(), state.clone())
+                .into_path()
+                .unwrap();
+            let actual = find_shortest_path_idastar(GameState::default(), state.clone())
+                .into_path()
+                .unwrap();
+            assert_eq!(actual.len(), expected.len());
+
+            let mut reached = GameState::default();
+            assert_eq!(reached.perform_moves(&actual), actual.len());
+            assert_eq!(reached, state);
+        }
+        let state = GameState::default();
+        assert_eq!(
+            find_shortest_path_idastar(state.clone(), state).outcome,
+            SolveOutcome::Solved(vec![])
+        );
+    }
+
+    #[test]
+    fn test_idastar_budget() {
+        let (state, _) = GameState::random_walk(40, 99);
+        let full = find_shortest_path_idastar(GameState::default(), state.clone());
+        let budgeted =
+            find_shortest_path_idastar_budget(GameState::default(), state.clone(), u64::MAX);
+        assert_eq!(budgeted.outcome, full.outcome);
+
+        let report = find_shortest_path_idastar_budget(GameState::default(), state.clone(), 100);
+        assert_eq!(report.stats.nodes_expanded, 100);
+        let SolveOutcome::BudgetExhausted {
+            closest,
+            path,
+            distance,
+        } = report.outcome
+        else {
+            panic!("expected the budget to run out");
+        };
+        let mut reached = GameState::default();
+        assert_eq!(reached.perform_moves(&path), path.len());
+        assert_eq!(reached, closest);
+        assert_eq!(Manhattan::new(&state).estimate(&closest), distance);
+        assert!(distance < Manhattan::new(&state).estimate(&GameState::default()));
+
+        // the trivial cases need no budget at all
+        let report = find_shortest_path_idastar_budget(state.clone(), state, 0);
+        assert_eq!(report.outcome, SolveOutcome::Solved(vec![]));
+    }
+
+    #[test]
+    fn test_unsolvable() {
+        let pdb = AdditivePdb::build(&[&[1, 2]]).unwrap();
+        let mut invalid = GameState::default();
+        invalid.set(0, 0, Some(16));
+        let mut swapped = GameState::default();
+        swapped.swap(1, 3, 2, 3);
+        // a deep scramble of the swapped board to make sure nothing gets explored
+        let mut far = swapped.clone();
+        let (_, moves) = GameState::random_walk(60, 17);
+        far.perform_moves(&moves);
+        for to in [invalid, swapped, far] {
+            let from = GameState::default();
+            for report in [
+                find_shortest_path_astar(from.clone(), to.clone()),
+                find_shortest_path_idastar(from.clone(), to.clone()),
+                find_shortest_path_astar_pdb(from.clone(), to.clone(), &pdb),
+                find_shortest_path_idastar_pdb(from.clone(), to.clone(), &pdb),
+            ] {
+                assert_eq!(report.outcome, SolveOutcome::Unsolvable);
+                assert_eq!(report.stats.nodes_expanded, 0);
+            }
+        }
+    }
+
+    /// Solves towards a partial goal with every solver that takes one and checks them against
+    /// the breadth first search.
+    fn check_goal<const W: usize, const H: usize>(from: &Board<W, H>, goal: &Goal<W, H>) {
+        let expected = find_shortest_path_to_goal(from.clone(), goal)
+            .into_path()
+            .unwrap();
+        for report in [
+            find_shortest_path_astar_to_goal(from.clone(), goal),
+            find_shortest_path_idastar_to_goal(from.clone(), goal),
+        ] {
+            let path = report.into_path().unwrap();
+            assert_eq!(path.len(), expected.len());
+            let mut reached = from.clone();
+            assert_eq!(reached.perform_moves(&path), path.len());
+            assert!(goal.is_reached(&reached));
+        }
+    }
+
+    #[test]
+    fn test_partial_goals() {
+        let top_row = Goal::partial(&GameState::default(), &[1, 2, 3, 4]);
+        let mut blank_in_corner = Goal::any();
+        blank_in_corner.set(0, 0, GoalCell::Blank);
+        let mut first_column = Goal::partial(&GameState::default(), &[1, 5, 9, 13]);
+        first_column.set(1, 0, GoalCell::Blank);
+        for seed in [4, 11, 23] {
+            let (state, _) = GameState::random_walk(14, seed);
+            for goal in [&top_row, &blank_in_corner, &first_column] {
+                check_goal(&state, goal);
+            }
+        }
+        // the blank has to cross the whole board, nothing else matters
+        let report = find_shortest_path_astar_to_goal(GameState::default(), &blank_in_corner);
+        assert_eq!(report.into_path().unwrap().len(), 6);
+
+        let (state, _) = Board::<3, 3>::random_walk(16, 5);
+        check_goal(&state, &Goal::partial(&Board::default(), &[1, 2, 3]));
+        let mut center = Goal::<3, 3>::any();
+        center.set(1, 1, GoalCell::Blank);
+        check_goal(&state, &center);
+        let (state, _) = Board::<5, 4>::random_walk(12, 6);
+        check_goal(&state, &Goal::partial(&Board::default(), &[1, 2, 6, 7]));
+    }
+
+    #[test]
+    fn test_manhattan_partial_goal() {
+        let goal = Goal::partial(&GameState::default(), &[1, 2]);
+        let heuristic = Manhattan::for_goal(&goal);
+        let mut state = GameState::default();
+        // tile 1 is two steps away, the other tiles don't count
+        state.swap(0, 0, 1, 1);
+        state.swap(3, 0, 3, 3);
+        assert_eq!(heuristic.estimate(&state), 2);
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        // 2 and 1 swapped with everything else fixed has the wrong parity
+        let mut swapped = GameState::default();
+        swapped.swap(0, 0, 1, 0);
+        let goal = Goal::partial(&swapped, &(1..15).collect::<Vec<u8>>());
+        let from = GameState::default();
+        for report in [
+            find_shortest_path_to_goal(from.clone(), &goal),
+            find_shortest_path_astar_to_goal(from.clone(), &goal),
+            find_shortest_path_idastar_to_goal(from.clone(), &goal),
+        ] {
+            assert_eq!(report.outcome, SolveOutcome::Unsolvable);
+            assert_eq!(report.stats.nodes_expanded, 0);
+        }
+        // already there
+        let report = find_shortest_path_idastar_to_goal(from, &Goal::any());
+        assert_eq!(report.outcome, SolveOutcome::Solved(vec![]));
+    }
+
+    #[test]
+    fn test_pdb_solvers() {
+        let pdb = AdditivePdb::build(&[&[1, 2, 3], &[5, 6, 9], &[4, 8, 12]]).unwrap();
+        for seed in [5, 77] {
+            let (state, _) = GameState::random_walk(24, seed);
+            let expected = find_shortest_path_idastar(state.clone(), GameState::default())
+                .into_path()
+                .unwrap()
+                .len();
+            let path = find_shortest_path_idastar_pdb(state.clone(), GameState::default(), &pdb)
+                .into_path()
+                .unwrap();
+            assert_eq!(path.len(), expected);
+            let mut reached = state.clone();
+            assert_eq!(reached.perform_moves(&path), path.len());
+            assert_eq!(reached, GameState::default());
+
+            let path = find_shortest_path_astar_pdb(state.clone(), GameState::default(), &pdb)
+                .into_path()
+                .unwrap();
+            assert_eq!(path.len(), expected);
+
+            // the tiles of the goal do not need to be in the default order
+            let (goal, _) = GameState::random_walk(20, seed + 1);
+            let expected = find_shortest_path_idastar(state.clone(), goal.clone()).into_path();
+            let expected = expected.map(|p| p.len());
+            let actual = find_shortest_path_idastar_pdb(state.clone(), goal.clone(), &pdb);
+            assert_eq!(actual.into_path().map(|p| p.len()), expected);
+            let actual = find_shortest_path_astar_pdb(state, goal, &pdb);
+            assert_eq!(actual.into_path().map(|p| p.len()), expected);
+        }
+    }
+
+    /// Builds the 5-5-5 databases and solves deep scrambles with them, run with
+    /// `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore]
+    fn test_idastar_pdb_5_5_5() {
+        let pdb = AdditivePdb::build(&crate::PARTITION_5_5_5).unwrap();
+        for seed in [11, 12, 13] {
+            let (state, _) = GameState::random_walk(300, seed);
+            let expected = find_shortest_path_idastar(state.clone(), GameState::default())
+                .into_path()
+                .unwrap()
+                .len();
+            let path = find_shortest_path_idastar_pdb(state.clone(), GameState::default(), &pdb)
+                .into_path()
+                .unwrap();
+            assert_eq!(path.len(), expected);
+            let mut reached = state;
+            assert_eq!(reached.perform_moves(&path), path.len());
+            assert_eq!(reached, GameState::default());
+        }
+    }
+
+    /// Korf's 100 classic benchmark instances (Korf, 1985), tiles in row-major order with the
+    /// blank in the top left corner of the goal, together with their optimal lengths.
+    const KORF: [([u8; 16], usize); 100] = [
+        ([14, 13, 15, 7, 11, 12, 9, 5, 6, 0, 2, 1, 4, 8, 10, 3], 57),
+        ([13, 5, 4, 10, 9, 12, 8, 14, 2, 3, 7, 1, 0, 15, 11, 6], 55),
+        ([14, 7, 8, 2, 13, 11, 10, 4, 9, 12, 5, 0, 3, 6, 1, 15], 59),
+        ([5, 12, 10, 7, 15, 11, 14, 0, 8, 2, 1, 13, 3, 4, 9, 6], 56),
+        ([4, 7, 14, 13, 10, 3, 9, 12, 11, 5, 6, 15, 1, 2, 8, 0], 56),
+        ([14, 7, 1, 9, 12, 3, 6, 15, 8, 11, 2, 5, 10, 0, 4, 13], 52),
+        ([2, 11, 15, 5, 13, 4, 6, 7, 12, 8, 10, 1, 9, 3, 14, 0], 52),
+        ([12, 11, 15, 3, 8, 0, 4, 2, 6, 13, 9, 5, 14, 1, 10, 7], 50),
+        ([3, 14, 9, 11, 5, 4, 8, 2, 13, 12, 6, 7, 10, 1, 15, 0], 46),
+        ([13, 11, 8, 9, 0, 15, 7, 10, 4, 3, 6, 14, 5, 12, 2, 1], 59),
+        ([5, 9, 13, 14, 6, 3, 7, 12, 10, 8, 4, 0, 15, 2, 11, 1], 57),
+        ([14, 1, 9, 6, 4, 8, 12, 5, 7, 2, 3, 0, 10, 11, 13, 15], 45),
+        ([3, 6, 5, 2, 10, 0, 15, 14, 1, 4, 13, 12, 9, 8, 11, 7], 46),
+        ([7, 6, 8, 1, 11, 5, 14, 10, 3, 4, 9, 13, 15, 2, 0, 12], 59),
+        ([13, 11, 4, 12, 1, 8, 9, 15, 6, 5, 14, 2, 7, 3, 10, 0], 62),
+        ([1, 3, 2, 5, 10, 9, 15, 6, 8, 14, 13, 11, 12, 4, 7, 0], 42),
+        ([15, 14, 0, 4, 11, 1, 6, 13, 7, 5, 8, 9, 3, 2, 10, 12], 66),
+        ([6, 0, 14, 12, 1, 15, 9, 10, 11, 4, 7, 2, 8, 3, 5, 13], 55),
+        ([7, 11, 8, 3, 14, 0, 6, 15, 1, 4, 13, 9, 5, 12, 2, 10], 46),
+        ([6, 12, 11, 3, 13, 7, 9, 15, 2, 14, 8, 10, 4, 1, 5, 0], 52),
+        ([12, 8, 14, 6, 11, 4, 7, 0, 5, 1, 10, 15, 3, 13, 9, 2], 54),
+        ([14, 3, 9, 1, 15, 8, 4, 5, 11, 7, 10, 13, 0, 2, 12, 6], 59),
+        ([10, 9, 3, 11, 0, 13, 2, 14, 5, 6, 4, 7, 8, 15, 1, 12], 49),
+        ([7, 3, 14, 13, 4, 1, 10, 8, 5, 12, 9, 11, 2, 15, 6, 0], 54),
+        ([11, 4, 2, 7, 1, 0, 10, 15, 6, 9, 14, 8, 3, 13, 5, 12], 52),
+        ([5, 7, 3, 12, 15, 13, 14, 8, 0, 10, 9, 6, 1, 4, 2, 11], 58),
+        ([14, 1, 8, 15, 2, 6, 0, 3, 9, 12, 10, 13, 4, 7, 5, 11], 53),
+        ([13, 14, 6, 12, 4, 5, 1, 0, 9, 3, 10, 2, 15, 11, 8, 7], 52),
+        ([9, 8, 0, 2, 15, 1, 4, 14, 3, 10, 7, 5, 11, 13, 6, 12], 54),
+        ([12, 15, 2, 6, 1, 14, 4, 8, 5, 3, 7, 0, 10, 13, 9, 11], 47),
+        ([12, 8, 15, 13, 1, 0, 5, 4, 6, 3, 2, 11, 9, 7, 14, 10], 50),
+        ([14, 10, 9, 4, 13, 6, 5, 8, 2, 12, 7, 0, 1, 3, 11, 15], 59),
+        ([14, 3, 5, 15, 11, 6, 13, 9, 0, 10, 2, 12, 4, 1, 7, 8], 60),
+        ([6, 11, 7, 8, 13, 2, 5, 4, 1, 10, 3, 9, 14, 0, 12, 15], 52),
+        ([1, 6, 12, 14, 3, 2, 15, 8, 4, 5, 13, 9, 0, 7, 11, 10], 55),
+        ([12, 6, 0, 4, 7, 3, 15, 1, 13, 9, 8, 11, 2, 14, 5, 10], 52),
+        ([8, 1, 7, 12, 11, 0, 10, 5, 9, 15, 6, 13, 14, 2, 3, 4], 58),
+        ([7, 15, 8, 2, 13, 6, 3, 12, 11, 0, 4, 10, 9, 5, 1, 14], 53),
+        ([9, 0, 4, 10, 1, 14, 15, 3, 12, 6, 5, 7, 11, 13, 8, 2], 49),
+        ([11, 5, 1, 14, 4, 12, 10, 0, 2, 7, 13, 3, 9, 15, 6, 8], 54),
+        ([8, 13, 10, 9, 11, 3, 15, 6, 0, 1, 2, 14, 12, 5, 4, 7], 54),
+        ([4, 5, 7, 2, 9, 14, 12, 13, 0, 3, 6, 11, 8, 1, 15, 10], 42),
+        ([11, 15, 14, 13, 1, 9, 10, 4, 3, 6, 2, 12, 7, 5, 8, 0], 64),
+        ([12, 9, 0, 6, 8, 3, 5, 14, 2, 4, 11, 7, 10, 1, 15, 13], 50),
+        ([3, 14, 9, 7, 12, 15, 0, 4, 1, 8, 5, 6, 11, 10, 2, 13], 51),
+        ([8, 4, 6, 1, 14, 12, 2, 15, 13, 10, 9, 5, 3, 7, 0, 11], 49),
+        ([6, 10, 1, 14, 15, 8, 3, 5, 13, 0, 2, 7, 4, 9, 11, 12], 47),
+        ([8, 11, 4, 6, 7, 3, 10, 9, 2, 12, 15, 13, 0, 1, 5, 14], 49),
+        ([10, 0, 2, 4, 5, 1, 6, 12, 11, 13, 9, 7, 15, 3, 14, 8], 59),
+        ([12, 5, 13, 11, 2, 10, 0, 9, 7, 8, 4, 3, 14, 6, 15, 1], 53),
+        ([10, 2, 8, 4, 15, 0, 1, 14, 11, 13, 3, 6, 9, 7, 5, 12], 56),
+        ([10, 8, 0, 12, 3, 7, 6, 2, 1, 14, 4, 11, 15, 13, 9, 5], 56),
+        ([14, 9, 12, 13, 15, 4, 8, 10, 0, 2, 1, 7, 3, 11, 5, 6], 64),
+        ([12, 11, 0, 8, 10, 2, 13, 15, 5, 4, 7, 3, 6, 9, 14, 1], 56),
+        ([13, 8, 14, 3, 9, 1, 0, 7, 15, 5, 4, 10, 12, 2, 6, 11], 41),
+        ([3, 15, 2, 5, 11, 6, 4, 7, 12, 9, 1, 0, 13, 14, 10, 8], 55),
+        ([5, 11, 6, 9, 4, 13, 12, 0, 8, 2, 15, 10, 1, 7, 3, 14], 50),
+        ([5, 0, 15, 8, 4, 6, 1, 14, 10, 11, 3, 9, 7, 12, 2, 13], 51),
+        ([15, 14, 6, 7, 10, 1, 0, 11, 12, 8, 4, 9, 2, 5, 13, 3], 57),
+        ([11, 14, 13, 1, 2, 3, 12, 4, 15, 7, 9, 5, 10, 6, 8, 0], 66),
+        ([6, 13, 3, 2, 11, 9, 5, 10, 1, 7, 12, 14, 8, 4, 0, 15], 45),
+        ([4, 6, 12, 0, 14, 2, 9, 13, 11, 8, 3, 15, 7, 10, 1, 5], 57),
+        ([8, 10, 9, 11, 14, 1, 7, 15, 13, 4, 0, 12, 6, 2, 5, 3], 56),
+        ([5, 2, 14, 0, 7, 8, 6, 3, 11, 12, 13, 15, 4, 10, 9, 1], 51),
+        ([7, 8, 3, 2, 10, 12, 4, 6, 11, 13, 5, 15, 0, 1, 9, 14], 47),
+        ([11, 6, 14, 12, 3, 5, 1, 15, 8, 0, 10, 13, 9, 7, 4, 2], 61),
+        ([7, 1, 2, 4, 8, 3, 6, 11, 10, 15, 0, 5, 14, 12, 13, 9], 50),
+        ([7, 3, 1, 13, 12, 10, 5, 2, 8, 0, 6, 11, 14, 15, 4, 9], 51),
+        ([6, 0, 5, 15, 1, 14, 4, 9, 2, 13, 8, 10, 11, 12, 7, 3], 53),
+        ([15, 1, 3, 12, 4, 0, 6, 5, 2, 8, 14, 9, 13, 10, 7, 11], 52),
+        ([5, 7, 0, 11, 12, 1, 9, 10, 15, 6, 2, 3, 8, 4, 13, 14], 44),
+        ([12, 15, 11, 10, 4, 5, 14, 0, 13, 7, 1, 2, 9, 8, 3, 6], 56),
+        ([6, 14, 10, 5, 15, 8, 7, 1, 3, 4, 2, 0, 12, 9, 11, 13], 49),
+        ([14, 13, 4, 11, 15, 8, 6, 9, 0, 7, 3, 1, 2, 10, 12, 5], 56),
+        ([14, 4, 0, 10, 6, 5, 1, 3, 9, 2, 13, 15, 12, 7, 8, 11], 48),
+        ([15, 10, 8, 3, 0, 6, 9, 5, 1, 14, 13, 11, 7, 2, 12, 4], 57),
+        ([0, 13, 2, 4, 12, 14, 6, 9, 15, 1, 10, 3, 11, 5, 8, 7], 54),
+        ([3, 14, 13, 6, 4, 15, 8, 9, 5, 12, 10, 0, 2, 7, 1, 11], 53),
+        ([0, 1, 9, 7, 11, 13, 5, 3, 14, 12, 4, 2, 8, 6, 10, 15], 42),
+        ([11, 0, 15, 8, 13, 12, 3, 5, 10, 1, 4, 6, 14, 9, 7, 2], 57),
+        ([13, 0, 9, 12, 11, 6, 3, 5, 15, 8, 1, 10, 4, 14, 2, 7], 53),
+        ([14, 10, 2, 1, 13, 9, 8, 11, 7, 3, 6, 12, 15, 5, 4, 0], 62),
+        ([12, 3, 9, 1, 4, 5, 10, 2, 6, 11, 15, 0, 14, 7, 13, 8], 49),
+        ([15, 8, 10, 7, 0, 12, 14, 1, 5, 9, 6, 3, 13, 11, 4, 2], 55),
+        ([4, 7, 13, 10, 1, 2, 9, 6, 12, 8, 14, 5, 3, 0, 11, 15], 44),
+        ([6, 0, 5, 10, 11, 12, 9, 2, 1, 7, 4, 3, 14, 8, 13, 15], 45),
+        ([9, 5, 11, 10, 13, 0, 2, 1, 8, 6, 14, 12, 4, 7, 3, 15], 52),
+        ([15, 2, 12, 11, 14, 13, 9, 5, 1, 3, 8, 7, 0, 10, 6, 4], 65),
+        ([11, 1, 7, 4, 10, 13, 3, 8, 9, 14, 0, 15, 6, 5, 2, 12], 54),
+        ([5, 4, 7, 1, 11, 12, 14, 15, 10, 13, 8, 6, 2, 0, 9, 3], 50),
+        ([9, 7, 5, 2, 14, 15, 12, 10, 11, 3, 6, 1, 8, 13, 0, 4], 57),
+        ([3, 2, 7, 9, 0, 15, 12, 4, 6, 11, 5, 14, 8, 13, 10, 1], 57),
+        ([13, 9, 14, 6, 12, 8, 1, 2, 3, 4, 0, 7, 5, 10, 11, 15], 46),
+        ([5, 7, 11, 8, 0, 14, 9, 13, 10, 12, 3, 15, 6, 1, 4, 2], 53),
+        ([4, 3, 6, 13, 7, 15, 9, 0, 10, 5, 8, 11, 2, 12, 1, 14], 50),
+        ([1, 7, 15, 14, 2, 6, 4, 9, 12, 11, 13, 3, 0, 8, 5, 10], 49),
+        ([9, 14, 5, 7, 8, 15, 1, 2, 10, 4, 13, 6, 12, 0, 11, 3], 44),
+        ([0, 11, 3, 12, 5, 2, 1, 9, 8, 10, 14, 15, 7, 4, 13, 6], 54),
+        ([7, 15, 4, 0, 10, 9, 2, 5, 12, 11, 13, 6, 1, 3, 14, 8], 57),
+        ([11, 4, 0, 8, 6, 10, 5, 13, 12, 7, 14, 3, 1, 2, 9, 15], 54),
+    ];
+
+    fn korf_goal() -> GameState {
+        from_row_major([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15])
+    }
+
+    /// Solves `KORF[index]` and checks that the path is legal, reaches the goal and has the known
+    /// optimal length.
+    fn check_korf(index: usize, solve: impl Fn(GameState, GameState) -> SolveReport) {
+        let (tiles, optimal) = KORF[index];
+        let start = from_row_major(tiles);
+        let path = solve(start.clone(), korf_goal()).into_path().unwrap();
+        assert_eq!(path.len(), optimal, "instance {}", index + 1);
+        let mut reached = start;
+        assert_eq!(reached.perform_moves(&path), optimal);
+        assert_eq!(reached, korf_goal());
+    }
+
+    #[test]
+    fn test_idastar_korf_easy() {
+        assert_eq!(
+            KORF.iter().map(|&(_, optimal)| optimal).sum::<usize>(),
+            5305
+        );
+        // the three easiest instances, 41 and 42 moves
+        for index in [54, 15, 41] {
+            check_korf(index, find_shortest_path_idastar);
+        }
+    }
+
+    /// Takes a while, run with `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore]
+    fn test_idastar_korf() {
+        for index in 0..KORF.len() {
+            check_korf(index, find_shortest_path_idastar);
+        }
+    }
+}
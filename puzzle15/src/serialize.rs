@@ -0,0 +1,400 @@
+//! Serde support, behind the `serde` feature. Human readable formats get boards as arrays of rows
+//! like the JSON notation and moves as their letters, binary formats get one byte per cell and
+//! per move.
+
+use crate::notation::{Cell, Placer};
+use crate::{Board, Move, Record};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+impl<const W: usize, const H: usize> Serialize for Board<W, H> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let tile = |x: usize, y: usize| self.get(x as u8, y as u8).unwrap_or(0);
+        if serializer.is_human_readable() {
+            let rows: Vec<Vec<u8>> = (0..H)
+                .map(|y| (0..W).map(|x| tile(x, y)).collect())
+                .collect();
+            rows.serialize(serializer)
+        } else {
+            let tiles: Vec<u8> = (0..H)
+                .flat_map(|y| (0..W).map(move |x| (x, y)))
+                .map(|(x, y)| tile(x, y))
+                .collect();
+            serializer.serialize_bytes(&tiles)
+        }
+    }
+}
+
+/// Reads the tiles of the binary form, which some formats write as a sequence instead of bytes.
+struct TilesVisitor;
+
+impl<'de> Visitor<'de> for TilesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "the tiles of a board as bytes")
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        Ok(bytes.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(bytes)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut tiles = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(tile) = seq.next_element()? {
+            tiles.push(tile);
+        }
+        Ok(tiles)
+    }
+}
+
+/// A tile of the human readable form, `null` is read as 0 for the blank.
+struct Tile(i64);
+
+struct TileVisitor;
+
+impl<'de> Visitor<'de> for TileVisitor {
+    type Value = Tile;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a tile number, or 0 or null for the blank")
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Tile, E> {
+        Ok(Tile(0))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Tile, E> {
+        Ok(Tile(0))
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Tile, D::Error> {
+        i64::deserialize(deserializer).map(Tile)
+    }
+
+    fn visit_i64<E: de::Error>(self, n: i64) -> Result<Tile, E> {
+        Ok(Tile(n))
+    }
+
+    fn visit_u64<E: de::Error>(self, n: u64) -> Result<Tile, E> {
+        i64::try_from(n)
+            .map(Tile)
+            .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(n), &self))
+    }
+}
+
+impl<'de> Deserialize<'de> for Tile {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_option(TileVisitor)
+    }
+}
+
+/// Checks the tiles with the same rules as the parsers. The blank can be `0` or, in human readable
+/// formats, `null` like in the JSON notation. The errors name rows as lines and count both from 1.
+impl<'de, const W: usize, const H: usize> Deserialize<'de> for Board<W, H> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tiles: Vec<i64> = if deserializer.is_human_readable() {
+            let rows = Vec::<Vec<Tile>>::deserialize(deserializer)?;
+            if rows.len() != H {
+                return Err(de::Error::invalid_length(
+                    rows.len(),
+                    &format!("{H} rows").as_str(),
+                ));
+            }
+            if let Some(row) = rows.iter().find(|row| row.len() != W) {
+                return Err(de::Error::invalid_length(
+                    row.len(),
+                    &format!("{W} tiles per row").as_str(),
+                ));
+            }
+            rows.into_iter().flatten().map(|Tile(tile)| tile).collect()
+        } else {
+            let tiles = deserializer.deserialize_bytes(TilesVisitor)?;
+            if tiles.len() != W * H {
+                return Err(de::Error::invalid_length(
+                    tiles.len(),
+                    &format!("{} tiles", W * H).as_str(),
+                ));
+            }
+            tiles.into_iter().map(i64::from).collect()
+        };
+
+        let mut placer = Placer::new();
+        for (i, tile) in tiles.into_iter().enumerate() {
+            let cell = Cell {
+                line: i / W + 1,
+                column: i % W + 1,
+                tile: (tile != 0).then_some(tile),
+            };
+            placer.place(cell).map_err(de::Error::custom)?;
+        }
+        Ok(placer.finish())
+    }
+}
+
+impl Serialize for Move {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_char(self.letter())
+        } else {
+            serializer.serialize_u8(*self as u8)
+        }
+    }
+}
+
+struct MoveVisitor;
+
+impl<'de> Visitor<'de> for MoveVisitor {
+    type Value = Move;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "one of the move letters R, L, D and U, or a number below 4"
+        )
+    }
+
+    fn visit_char<E: de::Error>(self, c: char) -> Result<Move, E> {
+        Move::from_letter(c).ok_or_else(|| E::invalid_value(de::Unexpected::Char(c), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Move, E> {
+        s.parse()
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(s), &self))
+    }
+
+    fn visit_u64<E: de::Error>(self, n: u64) -> Result<Move, E> {
+        match n {
+            0 => Ok(Move::LeftToRight),
+            1 => Ok(Move::RightToLeft),
+            2 => Ok(Move::TopToBottom),
+            3 => Ok(Move::BottomToTop),
+            _ => Err(E::invalid_value(de::Unexpected::Unsigned(n), &self)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Move {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_char(MoveVisitor)
+        } else {
+            deserializer.deserialize_u8(MoveVisitor)
+        }
+    }
+}
+
+impl<const W: usize, const H: usize> Serialize for Record<W, H> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut record = serializer.serialize_struct("Record", 2)?;
+        record.serialize_field("start", &self.start)?;
+        record.serialize_field("moves", &self.moves)?;
+        record.end()
+    }
+}
+
+struct RecordVisitor<const W: usize, const H: usize>;
+
+impl<'de, const W: usize, const H: usize> Visitor<'de> for RecordVisitor<W, H> {
+    type Value = Record<W, H>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a record with a start and moves")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let start = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let moves = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Ok(Record { start, moves })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let (mut start, mut moves) = (None, None);
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "start" if start.is_none() => start = Some(map.next_value()?),
+                "moves" if moves.is_none() => moves = Some(map.next_value()?),
+                "start" | "moves" => {
+                    return Err(de::Error::custom(format!("duplicate field `{key}`")))
+                }
+                _ => return Err(de::Error::unknown_field(&key, RECORD_FIELDS)),
+            }
+        }
+        Ok(Record {
+            start: start.ok_or_else(|| de::Error::missing_field("start"))?,
+            moves: moves.ok_or_else(|| de::Error::missing_field("moves"))?,
+        })
+    }
+}
+
+const RECORD_FIELDS: &[&str] = &["start", "moves"];
+
+/// Only checks the start and the moves one by one, `Record::verify` tells if the moves fit.
+impl<'de, const W: usize, const H: usize> Deserialize<'de> for Record<W, H> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("Record", RECORD_FIELDS, RecordVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameState;
+    use serde_test::{assert_de_tokens, assert_de_tokens_error, assert_tokens, Configure, Token};
+
+    #[test]
+    fn test_json() {
+        let mut state = GameState::default();
+        state.perform_moves(&Move::parse_sequence("RD").unwrap());
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(json, "[[1,2,3,4],[5,6,7,8],[9,10,0,12],[13,14,11,15]]");
+        assert_eq!(serde_json::from_str::<GameState>(&json).unwrap(), state);
+
+        let moves = Move::parse_sequence("RDLU").unwrap();
+        let json = serde_json::to_string(&moves).unwrap();
+        assert_eq!(json, r#"["R","D","L","U"]"#);
+        assert_eq!(serde_json::from_str::<Vec<Move>>(&json).unwrap(), moves);
+
+        let eight: Board<3, 3> = serde_json::from_str("[[1,2,3],[4,5,6],[7,8,0]]").unwrap();
+        assert_eq!(eight, Board::default());
+
+        // the JSON notation and serde read each other's boards, with the blank as 0 or null
+        let notation = state.to_notation(crate::Notation::Json).unwrap();
+        assert_eq!(serde_json::from_str::<GameState>(&notation).unwrap(), state);
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(GameState::parse_any(&json), Ok(state.clone()));
+        let null = "[[1,2,3,4],[5,6,7,8],[9,10,null,12],[13,14,11,15]]";
+        assert_eq!(serde_json::from_str::<GameState>(null).unwrap(), state);
+        assert_eq!(GameState::parse_any(null), Ok(state.clone()));
+
+        let record = Record {
+            start: eight,
+            moves: Move::parse_sequence("RD").unwrap(),
+        };
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(
+            json,
+            r#"{"start":[[1,2,3],[4,5,6],[7,8,0]],"moves":["R","D"]}"#
+        );
+        assert_eq!(serde_json::from_str::<Record<3, 3>>(&json).unwrap(), record);
+        assert_eq!(
+            serde_json::from_str::<Record<3, 3>>(r#"{"start":[[1,2,3],[4,5,6],[7,8,0]]}"#)
+                .unwrap_err()
+                .to_string(),
+            "missing field `moves` at line 1 column 35"
+        );
+    }
+
+    #[test]
+    fn test_tokens() {
+        let board = Board::<2, 2>::default();
+        assert_tokens(
+            &board.clone().readable(),
+            &[
+                Token::Seq { len: Some(2) },
+                Token::Seq { len: Some(2) },
+                Token::U8(1),
+                Token::U8(2),
+                Token::SeqEnd,
+                Token::Seq { len: Some(2) },
+                Token::U8(3),
+                Token::U8(0),
+                Token::SeqEnd,
+                Token::SeqEnd,
+            ],
+        );
+        assert_de_tokens(
+            &board.clone().readable(),
+            &[
+                Token::Seq { len: Some(2) },
+                Token::Seq { len: Some(2) },
+                Token::U8(1),
+                Token::U8(2),
+                Token::SeqEnd,
+                Token::Seq { len: Some(2) },
+                Token::Some,
+                Token::U8(3),
+                Token::None,
+                Token::SeqEnd,
+                Token::SeqEnd,
+            ],
+        );
+        assert_tokens(&board.clone().compact(), &[Token::Bytes(&[1, 2, 3, 0])]);
+        assert_tokens(&Move::TopToBottom.readable(), &[Token::Char('D')]);
+        assert_tokens(&Move::TopToBottom.compact(), &[Token::U8(2)]);
+        let record = Record {
+            start: board,
+            moves: vec![Move::LeftToRight],
+        };
+        assert_tokens(
+            &record.compact(),
+            &[
+                Token::Struct {
+                    name: "Record",
+                    len: 2,
+                },
+                Token::Str("start"),
+                Token::Bytes(&[1, 2, 3, 0]),
+                Token::Str("moves"),
+                Token::Seq { len: Some(1) },
+                Token::U8(0),
+                Token::SeqEnd,
+                Token::StructEnd,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_validation() {
+        let error = |json| {
+            serde_json::from_str::<GameState>(json)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error("[[1,2,3,4],[5,6,7,8],[9,10,11,12],[13,14,15,15]]"),
+            "line 4, column 4: tile 15 appears twice"
+        );
+        assert_eq!(
+            error("[[1,2,3,4],[5,6,7,8],[9,10,11,12],[13,14,0,0]]"),
+            "line 4, column 4: second empty cell"
+        );
+        assert_eq!(
+            error("[[1,2,3,4],[5,6,7,8],[9,10,11,12],[13,14,16,0]]"),
+            "line 4, column 3: tile 16 is not between 1 and 15"
+        );
+        assert_eq!(
+            error("[[1,2,3,4],[5,6,7,8],[9,10,11,12]]"),
+            "invalid length 3, expected 4 rows"
+        );
+        assert_eq!(
+            error("[[1,2,3,4],[5,6,7,8],[9,10,11,12],[13,14,15]]"),
+            "invalid length 3, expected 4 tiles per row"
+        );
+
+        assert_de_tokens_error::<serde_test::Compact<Board<2, 2>>>(
+            &[Token::Bytes(&[1, 2, 2, 0])],
+            "line 2, column 1: tile 2 appears twice",
+        );
+        assert_de_tokens_error::<serde_test::Compact<Board<2, 2>>>(
+            &[Token::Bytes(&[1, 2, 3])],
+            "invalid length 3, expected 4 tiles",
+        );
+        assert_de_tokens_error::<serde_test::Readable<Move>>(
+            &[Token::Char('X')],
+            "invalid value: character `X`, expected one of the move letters R, L, D and U, or a number below 4",
+        );
+        assert_de_tokens_error::<serde_test::Compact<Move>>(
+            &[Token::U8(4)],
+            "invalid value: integer `4`, expected one of the move letters R, L, D and U, or a number below 4",
+        );
+    }
+}
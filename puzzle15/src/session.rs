@@ -0,0 +1,327 @@
+use crate::{Board, Move, ParseGameStateError, ParseMoveError};
+use std::str::FromStr;
+
+/// A game that remembers every move together with the state it led to, so it can go back and
+/// forth through them. Undoing keeps the undone moves around for redoing until a new move is made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session<const W: usize = 4, const H: usize = 4> {
+    start: Board<W, H>,
+    /// every move that was made with the state after it, including the undone ones
+    plies: Vec<(Move, Board<W, H>)>,
+    /// the number of moves that are not undone
+    ply: usize,
+}
+
+impl<const W: usize, const H: usize> Session<W, H> {
+    pub fn new(start: Board<W, H>) -> Self {
+        Session {
+            start,
+            plies: vec![],
+            ply: 0,
+        }
+    }
+
+    pub fn start(&self) -> &Board<W, H> {
+        &self.start
+    }
+
+    pub fn state(&self) -> &Board<W, H> {
+        match self.ply {
+            0 => &self.start,
+            ply => &self.plies[ply - 1].1,
+        }
+    }
+
+    /// Number of moves made up to the current state.
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// Number of moves recorded, including the ones that can be redone.
+    pub fn len(&self) -> usize {
+        self.plies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plies.is_empty()
+    }
+
+    /// The moves made up to the current state, each with the state it led to.
+    pub fn history(&self) -> &[(Move, Board<W, H>)] {
+        &self.plies[..self.ply]
+    }
+
+    /// Makes a move from the current state. Returns false and changes nothing if the move is not
+    /// possible. Otherwise the moves that could be redone are forgotten.
+    pub fn perform_move(&mut self, m: Move) -> bool {
+        let mut state = self.state().clone();
+        if !state.perform_move(m) {
+            return false;
+        }
+        self.plies.truncate(self.ply);
+        self.plies.push((m, state));
+        self.ply += 1;
+        true
+    }
+
+    /// Makes a series of moves, skipping the impossible ones like `Board::perform_moves`.
+    /// Returns the number of moves that were successful.
+    pub fn perform_moves(&mut self, moves: &[Move]) -> usize {
+        moves.iter().filter(|&&m| self.perform_move(m)).count()
+    }
+
+    /// Takes back the last move and returns it, None if the session is at the start.
+    pub fn undo(&mut self) -> Option<Move> {
+        self.ply = self.ply.checked_sub(1)?;
+        Some(self.plies[self.ply].0)
+    }
+
+    /// Makes the last undone move again and returns it, None if there is nothing to redo.
+    pub fn redo(&mut self) -> Option<Move> {
+        let (m, _) = self.plies.get(self.ply)?;
+        self.ply += 1;
+        Some(*m)
+    }
+
+    /// Goes to the state after `ply` moves, backwards or forwards. Returns false if fewer moves
+    /// are recorded.
+    pub fn go_to(&mut self, ply: usize) -> bool {
+        if ply > self.plies.len() {
+            return false;
+        }
+        self.ply = ply;
+        true
+    }
+
+    /// The start and the moves up to the current state. Moves that can be redone are left out.
+    pub fn record(&self) -> Record<W, H> {
+        Record {
+            start: self.start.clone(),
+            moves: self.history().iter().map(|(m, _)| *m).collect(),
+        }
+    }
+}
+
+/// All there is to know about a game: where it started and the moves made from there.
+///
+/// As text it is the start in the flat notation on the first line and the moves as letters
+/// on the second, for example
+/// ```text
+/// 1 2 3 4 5 6 7 8 9 10 11 12 13 14 0 15
+/// LDR
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Record<const W: usize = 4, const H: usize = 4> {
+    pub start: Board<W, H>,
+    pub moves: Vec<Move>,
+}
+
+/// Why a record could not be replayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The move with this index (counting from 0) is not possible in the state before it.
+    IllegalMove { ply: usize, m: Move },
+    /// All moves were possible but the game ended somewhere else than claimed.
+    WrongFinalState,
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::IllegalMove { ply, m } => {
+                write!(f, "move {} ({m}) is not possible", ply + 1)
+            }
+            ReplayError::WrongFinalState => write!(f, "the moves end in a different state"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl<const W: usize, const H: usize> Record<W, H> {
+    /// Makes the moves from the start, failing at the first one that is not possible.
+    pub fn replay(&self) -> Result<Session<W, H>, ReplayError> {
+        let mut session = Session::new(self.start.clone());
+        for (ply, &m) in self.moves.iter().enumerate() {
+            if !session.perform_move(m) {
+                return Err(ReplayError::IllegalMove { ply, m });
+            }
+        }
+        Ok(session)
+    }
+
+    /// Replays the record and checks that it ends in `claimed`.
+    pub fn verify(&self, claimed: &Board<W, H>) -> Result<(), ReplayError> {
+        match self.replay()?.state() == claimed {
+            true => Ok(()),
+            false => Err(ReplayError::WrongFinalState),
+        }
+    }
+}
+
+impl<const W: usize, const H: usize> std::fmt::Display for Record<W, H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let start = self.start.to_notation(crate::Notation::Flat).unwrap();
+        writeln!(f, "{start}")?;
+        writeln!(f, "{}", Move::format_sequence(&self.moves))
+    }
+}
+
+/// Why a record could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseRecordError {
+    /// The first line is not a board.
+    Start(ParseGameStateError),
+    /// The second line is not a sequence of moves.
+    Moves(ParseMoveError),
+    /// There is something after the moves, on this line (counting from 1).
+    TrailingLine(usize),
+}
+
+impl std::fmt::Display for ParseRecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseRecordError::Start(error) => write!(f, "invalid start: {error}"),
+            ParseRecordError::Moves(error) => write!(f, "invalid moves: {error}"),
+            ParseRecordError::TrailingLine(line) => {
+                write!(f, "line {line}: expected nothing after the moves")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseRecordError {}
+
+/// Reads the start in any single line notation. The line with the moves can be left out if
+/// there are none.
+impl<const W: usize, const H: usize> FromStr for Record<W, H> {
+    type Err = ParseRecordError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let start = lines.next().map_or("", |(_, line)| line);
+        let start = Board::parse_any(start).map_err(ParseRecordError::Start)?;
+        let moves = match lines.next() {
+            Some((_, line)) => Move::parse_sequence(line).map_err(ParseRecordError::Moves)?,
+            None => vec![],
+        };
+        if let Some((index, _)) = lines.next() {
+            return Err(ParseRecordError::TrailingLine(index + 1));
+        }
+        Ok(Record { start, moves })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameState;
+
+    fn moves(s: &str) -> Vec<Move> {
+        Move::parse_sequence(s).unwrap()
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut session = Session::new(GameState::default());
+        assert_eq!(session.perform_moves(&moves("RRUD")), 3);
+        assert_eq!((session.ply(), session.len()), (3, 3));
+        let mut expected = GameState::default();
+        expected.perform_moves(&moves("RRD"));
+        assert_eq!(session.state(), &expected);
+        assert_eq!(session.history()[1].0, Move::LeftToRight);
+
+        assert_eq!(session.undo(), Some(Move::TopToBottom));
+        assert_eq!(session.undo(), Some(Move::LeftToRight));
+        assert_eq!((session.ply(), session.len()), (1, 3));
+        assert_eq!(session.redo(), Some(Move::LeftToRight));
+        assert_eq!(session.redo(), Some(Move::TopToBottom));
+        assert_eq!(session.redo(), None);
+        assert_eq!(session.state(), &expected);
+
+        assert!(session.go_to(0));
+        assert_eq!(session.state(), &GameState::default());
+        assert_eq!(session.undo(), None);
+        assert!(!session.go_to(4));
+        assert!(session.go_to(2));
+
+        // a blocked move keeps the moves to redo, a new move drops them
+        assert!(!session.perform_move(Move::BottomToTop));
+        assert_eq!(session.len(), 3);
+        assert!(session.perform_move(Move::RightToLeft));
+        assert_eq!((session.ply(), session.len()), (3, 3));
+        assert_eq!(session.redo(), None);
+        assert_eq!(session.record().moves, moves("RRL"));
+    }
+
+    #[test]
+    fn test_replay() {
+        let mut session = Session::new(GameState::default());
+        session.perform_moves(&moves("RDRL"));
+        session.undo();
+        let record = session.record();
+        assert_eq!(record.moves, moves("RDR"));
+        assert_eq!(record.replay().unwrap(), {
+            let mut replayed = Session::new(GameState::default());
+            replayed.perform_moves(&moves("RDR"));
+            replayed
+        });
+        assert_eq!(record.verify(session.state()), Ok(()));
+        assert_eq!(
+            record.verify(&GameState::default()),
+            Err(ReplayError::WrongFinalState)
+        );
+
+        let illegal = Record {
+            start: GameState::default(),
+            moves: moves("RUR"),
+        };
+        assert_eq!(
+            illegal.replay(),
+            Err(ReplayError::IllegalMove {
+                ply: 1,
+                m: Move::BottomToTop
+            })
+        );
+        assert_eq!(
+            illegal
+                .verify(&GameState::default())
+                .unwrap_err()
+                .to_string(),
+            "move 2 (U) is not possible"
+        );
+    }
+
+    #[test]
+    fn test_record_text() {
+        let record = Record {
+            start: GameState::default(),
+            moves: moves("RDL"),
+        };
+        let text = record.to_string();
+        assert_eq!(text, "1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 0\nRDL\n");
+        assert_eq!(text.parse(), Ok(record));
+
+        let empty: Record<3, 3> = "123456780".parse().unwrap();
+        assert_eq!((empty.start, empty.moves), (Board::default(), vec![]));
+
+        assert!(matches!(
+            "1 2 3\nR".parse::<Record>(),
+            Err(ParseRecordError::Start(_))
+        ));
+        assert_eq!(
+            "123456780\nRX"
+                .parse::<Record<3, 3>>()
+                .unwrap_err()
+                .to_string(),
+            "invalid moves: column 2: 'X' is not a move"
+        );
+        assert_eq!(
+            "123456780\nR\n\nL".parse::<Record<3, 3>>(),
+            Err(ParseRecordError::TrailingLine(4))
+        );
+    }
+}